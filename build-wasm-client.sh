@@ -110,7 +110,7 @@ END
 
 # Build
 export RUSTFLAGS='--cfg getrandom_backend="wasm_js"'
-cargo build --bin MissileSurvivor $BUILD_FLAGS --target wasm32-unknown-unknown
+cargo build --bin MissileSurvivor $BUILD_FLAGS --features audio,gamepad --target wasm32-unknown-unknown
 
 
 
@@ -20,7 +20,7 @@
           };
           rustToolchain = pkgs.pkgsBuildHost.rust-bin.fromRustupToolchainFile ./rust-toolchain.toml;
           nativeBuildInputs = with pkgs; [ rustToolchain pkg-config wasm-bindgen-cli ];
-          buildInputs = with pkgs; [ ];
+          buildInputs = with pkgs; [ alsa-lib udev ];
         in
         with pkgs;
         {
@@ -32,7 +32,7 @@
               export RUST_SRC_PATH=${pkgs.rustPlatform.rustLibSrc}
               export LD_LIBRARY_PATH="$LD_LIBRARY_PATH:${
                 with pkgs;
-                lib.makeLibraryPath [ libGL xorg.libX11 xorg.libXi libxkbcommon ]
+                lib.makeLibraryPath [ libGL xorg.libX11 xorg.libXi libxkbcommon alsa-lib udev ]
               }"
             '';
           };
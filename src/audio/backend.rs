@@ -0,0 +1,85 @@
+use super::Effect;
+
+/// Something that can play the sounds of the game
+pub trait AudioBackend: Send {
+  fn play_effect(&mut self, effect: Effect, volume: f32);
+  /// Start all music layers at once, so they stay in sync
+  fn start_music(&mut self, layer_volumes: &[f32]);
+  fn set_music_volumes(&mut self, layer_volumes: &[f32]);
+  fn stop_music(&mut self);
+}
+
+/// Plays nothing. Used without the `audio` feature and when the sounds could not be loaded.
+pub struct NullBackend;
+
+impl AudioBackend for NullBackend {
+  fn play_effect(&mut self, _effect: Effect, _volume: f32) {}
+  fn start_music(&mut self, _layer_volumes: &[f32]) {}
+  fn set_music_volumes(&mut self, _layer_volumes: &[f32]) {}
+  fn stop_music(&mut self) {}
+}
+
+#[cfg(feature = "audio")]
+pub use macroquad_backend::MacroquadBackend;
+
+#[cfg(feature = "audio")]
+mod macroquad_backend {
+  use std::collections::HashMap;
+
+  use macroquad::audio::{PlaySoundParams, Sound, load_sound_from_bytes, play_sound, set_sound_volume, stop_sound};
+
+  use super::AudioBackend;
+  use crate::audio::{Effect, MusicLayer, synth};
+
+  /// Plays the synthesized sounds with the audio support of macroquad
+  pub struct MacroquadBackend {
+    effects: HashMap<Effect, Sound>,
+    music_layers: Vec<Sound>,
+  }
+
+  async fn load(samples: &[f32]) -> Result<Sound, String> {
+    load_sound_from_bytes(&synth::encode_wav(samples))
+      .await
+      .map_err(|err| format!("Could not load sound: {err}"))
+  }
+
+  impl MacroquadBackend {
+    pub async fn load() -> Result<Self, String> {
+      let mut effects = HashMap::new();
+      for effect in Effect::all() {
+        effects.insert(effect, load(&effect.samples()).await?);
+      }
+      let mut music_layers = Vec::new();
+      for layer in MusicLayer::ALL {
+        music_layers.push(load(&layer.samples()).await?);
+      }
+      Ok(Self { effects, music_layers })
+    }
+  }
+
+  impl AudioBackend for MacroquadBackend {
+    fn play_effect(&mut self, effect: Effect, volume: f32) {
+      if let Some(sound) = self.effects.get(&effect) {
+        play_sound(sound, PlaySoundParams { looped: false, volume });
+      }
+    }
+
+    fn start_music(&mut self, layer_volumes: &[f32]) {
+      for (sound, &volume) in self.music_layers.iter().zip(layer_volumes) {
+        play_sound(sound, PlaySoundParams { looped: true, volume });
+      }
+    }
+
+    fn set_music_volumes(&mut self, layer_volumes: &[f32]) {
+      for (sound, &volume) in self.music_layers.iter().zip(layer_volumes) {
+        set_sound_volume(sound, volume);
+      }
+    }
+
+    fn stop_music(&mut self) {
+      for sound in &self.music_layers {
+        stop_sound(sound);
+      }
+    }
+  }
+}
@@ -0,0 +1,350 @@
+//! Sound effects and music. All sounds are synthesized when the game starts, so there are no audio files.
+//! Without the `audio` feature everything is played on a silent backend.
+
+use std::sync::{Mutex, MutexGuard};
+
+use lazy_static::lazy_static;
+
+use crate::game::constants::EXPLOSION_MAX_RADIUS;
+use crate::game::events::GameEvent;
+use crate::settings::Settings;
+
+mod backend;
+mod synth;
+
+pub use backend::{AudioBackend, NullBackend};
+
+lazy_static! {
+  static ref AUDIO: Mutex<Audio> = Mutex::new(Audio::new(Box::new(NullBackend)));
+}
+
+/// Number of pitches explosions are played at, bigger explosions sound deeper
+const EXPLOSION_PITCHES: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Effect {
+  Launch,
+  /// Explosion with the pitch index from 0 (deepest) to `EXPLOSION_PITCHES - 1`
+  Explosion(usize),
+  BunkerDestroyed,
+  StarCollected,
+  LevelUp,
+  GameOver,
+}
+
+impl Effect {
+  pub fn all() -> Vec<Effect> {
+    let mut effects = vec![
+      Effect::Launch,
+      Effect::BunkerDestroyed,
+      Effect::StarCollected,
+      Effect::LevelUp,
+      Effect::GameOver,
+    ];
+    effects.extend((0..EXPLOSION_PITCHES).map(Effect::Explosion));
+    effects
+  }
+
+  /// The explosion sound for an explosion that grows to `max_radius`
+  pub fn explosion(max_radius: f32) -> Self {
+    // Explosions of the default size get the second highest pitch, upgraded ones sound deeper
+    let size = max_radius / EXPLOSION_MAX_RADIUS;
+    let pitch = if size < 0.75 {
+      3
+    } else if size < 1.25 {
+      2
+    } else if size < 2.0 {
+      1
+    } else {
+      0
+    };
+    Effect::Explosion(pitch)
+  }
+
+  fn samples(self) -> Vec<f32> {
+    match self {
+      Effect::Launch => synth::launch(),
+      Effect::Explosion(pitch) => synth::explosion(0.6 + 0.4 * pitch as f32),
+      Effect::BunkerDestroyed => synth::bunker_destroyed(),
+      Effect::StarCollected => synth::star_collected(),
+      Effect::LevelUp => synth::level_up(),
+      Effect::GameOver => synth::game_over(),
+    }
+  }
+
+  // Loudness relative to the other effects
+  fn volume(self) -> f32 {
+    match self {
+      Effect::Launch => 0.3,
+      Effect::Explosion(_) => 0.5,
+      Effect::BunkerDestroyed | Effect::GameOver => 0.8,
+      Effect::StarCollected | Effect::LevelUp => 0.6,
+    }
+  }
+}
+
+/// The music is made of layers that fade in one after another as the run goes on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MusicLayer {
+  Bass,
+  Drums,
+  Lead,
+}
+
+impl MusicLayer {
+  const ALL: [MusicLayer; 3] = [MusicLayer::Bass, MusicLayer::Drums, MusicLayer::Lead];
+
+  fn samples(self) -> Vec<f32> {
+    match self {
+      MusicLayer::Bass => synth::music_bass(),
+      MusicLayer::Drums => synth::music_drums(),
+      MusicLayer::Lead => synth::music_lead(),
+    }
+  }
+
+  // Game time in seconds when the layer starts and finishes fading in
+  fn fade_in(self) -> (f32, f32) {
+    match self {
+      MusicLayer::Bass => (0.0, 5.0),
+      MusicLayer::Drums => (60.0, 90.0),
+      MusicLayer::Lead => (180.0, 210.0),
+    }
+  }
+
+  fn volume(self, game_time: f32) -> f32 {
+    let (start, end) = self.fade_in();
+    ((game_time - start) / (end - start)).clamp(0.0, 1.0)
+  }
+}
+
+/// Volume of each group of sounds, all from 0.0 to 1.0
+#[derive(Debug, Clone, Copy)]
+pub struct Mixer {
+  pub master: f32,
+  pub effects: f32,
+  pub music: f32,
+}
+
+impl Mixer {
+  pub fn from_settings() -> Self {
+    let settings = Settings::get();
+    Self {
+      master: settings.volume,
+      effects: settings.effects_volume,
+      music: settings.music_volume,
+    }
+  }
+
+  fn effect_volume(&self, effect: Effect) -> f32 {
+    self.master * self.effects * effect.volume()
+  }
+
+  fn music_volumes(&self, game_time: f32) -> Vec<f32> {
+    (MusicLayer::ALL.iter())
+      .map(|layer| self.master * self.music * layer.volume(game_time))
+      .collect()
+  }
+}
+
+pub struct Audio {
+  backend: Box<dyn AudioBackend>,
+  music_playing: bool,
+}
+
+impl Audio {
+  fn new(backend: Box<dyn AudioBackend>) -> Self {
+    Self {
+      backend,
+      music_playing: false,
+    }
+  }
+
+  pub fn get() -> MutexGuard<'static, Self> {
+    AUDIO.lock().unwrap()
+  }
+
+  /// Synthesize and load all sounds. The game stays silent if this fails.
+  pub async fn init() {
+    #[cfg(feature = "audio")]
+    match backend::MacroquadBackend::load().await {
+      Ok(backend) => *Self::get() = Self::new(Box::new(backend)),
+      Err(err) => log::error!("{err}, the game will be silent"),
+    }
+  }
+
+  pub fn play(&mut self, effect: Effect) {
+    let volume = Mixer::from_settings().effect_volume(effect);
+    self.backend.play_effect(effect, volume);
+  }
+
+  /// Play the sounds for the events of the last simulation steps.
+  /// Every sound is played at most once, so big chain reactions don't get deafening.
+  pub fn play_events(&mut self, events: &[GameEvent]) {
+    self.play_events_mixed(events, &Mixer::from_settings());
+  }
+
+  fn play_events_mixed(&mut self, events: &[GameEvent], mixer: &Mixer) {
+    let mut effects = Vec::new();
+    for event in events {
+      let effect = match *event {
+        GameEvent::MissileLaunched { .. } => Effect::Launch,
+        GameEvent::Explosion { max_radius, .. } => Effect::explosion(max_radius),
+        GameEvent::BunkerDestroyed { .. } => Effect::BunkerDestroyed,
+        GameEvent::StarCollected { .. } => Effect::StarCollected,
+        GameEvent::LevelUp => Effect::LevelUp,
+        GameEvent::GameOver => Effect::GameOver,
+        // The explosion of the missile is loud enough
+        GameEvent::MissileIntercepted { .. } => continue,
+      };
+      if !effects.contains(&effect) {
+        effects.push(effect);
+      }
+    }
+
+    for effect in effects {
+      self.backend.play_effect(effect, mixer.effect_volume(effect));
+    }
+  }
+
+  /// Start the music if it isn't playing yet. It gets more intense the longer the run lasts.
+  pub fn update_music(&mut self, game_time: f32) {
+    self.update_music_mixed(game_time, &Mixer::from_settings());
+  }
+
+  fn update_music_mixed(&mut self, game_time: f32, mixer: &Mixer) {
+    let volumes = mixer.music_volumes(game_time);
+    if self.music_playing {
+      self.backend.set_music_volumes(&volumes);
+    } else {
+      self.backend.start_music(&volumes);
+      self.music_playing = true;
+    }
+  }
+
+  pub fn stop_music(&mut self) {
+    if self.music_playing {
+      self.backend.stop_music();
+      self.music_playing = false;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::{Arc, Mutex};
+
+  use macroquad::prelude::Vec2;
+
+  use super::*;
+
+  #[derive(Debug, PartialEq)]
+  enum Played {
+    Effect(Effect, f32),
+    Music(Vec<f32>),
+    StopMusic,
+  }
+
+  // Remembers everything it was asked to play, so the tests can look at it
+  struct RecordingBackend(Arc<Mutex<Vec<Played>>>);
+
+  impl AudioBackend for RecordingBackend {
+    fn play_effect(&mut self, effect: Effect, volume: f32) {
+      self.0.lock().unwrap().push(Played::Effect(effect, volume));
+    }
+
+    fn start_music(&mut self, layer_volumes: &[f32]) {
+      self.0.lock().unwrap().push(Played::Music(layer_volumes.to_vec()));
+    }
+
+    fn set_music_volumes(&mut self, layer_volumes: &[f32]) {
+      self.0.lock().unwrap().push(Played::Music(layer_volumes.to_vec()));
+    }
+
+    fn stop_music(&mut self) {
+      self.0.lock().unwrap().push(Played::StopMusic);
+    }
+  }
+
+  fn recording_audio() -> (Audio, Arc<Mutex<Vec<Played>>>) {
+    let played = Arc::new(Mutex::new(Vec::new()));
+    (Audio::new(Box::new(RecordingBackend(played.clone()))), played)
+  }
+
+  const FULL_VOLUME: Mixer = Mixer {
+    master: 1.0,
+    effects: 1.0,
+    music: 1.0,
+  };
+
+  fn explosion(max_radius: f32) -> GameEvent {
+    GameEvent::Explosion {
+      pos: Vec2::ZERO,
+      max_radius,
+      hostile: false,
+    }
+  }
+
+  #[test]
+  fn the_null_backend_plays_without_an_audio_device() {
+    let mut audio = Audio::new(Box::new(NullBackend));
+    audio.play_events_mixed(&[GameEvent::LevelUp, GameEvent::GameOver], &FULL_VOLUME);
+    audio.update_music_mixed(10.0, &FULL_VOLUME);
+    assert!(audio.music_playing);
+    audio.stop_music();
+    assert!(!audio.music_playing);
+  }
+
+  #[test]
+  fn every_sound_is_played_once_per_frame() {
+    let (mut audio, played) = recording_audio();
+    let events = [
+      GameEvent::MissileLaunched { pos: Vec2::ZERO },
+      GameEvent::MissileLaunched { pos: Vec2::ONE },
+      explosion(EXPLOSION_MAX_RADIUS),
+      GameEvent::MissileIntercepted {
+        pos: Vec2::ZERO,
+        chain_kills: 1,
+      },
+      explosion(EXPLOSION_MAX_RADIUS),
+      explosion(EXPLOSION_MAX_RADIUS * 3.0),
+      GameEvent::StarCollected { pos: Vec2::ZERO },
+    ];
+    audio.play_events_mixed(&events, &FULL_VOLUME);
+
+    let effects: Vec<Effect> = (played.lock().unwrap().iter())
+      .filter_map(|played| match played {
+        Played::Effect(effect, _) => Some(*effect),
+        _ => None,
+      })
+      .collect();
+    // Intercepts are silent, bigger explosions are deeper
+    assert_eq!(
+      effects,
+      [Effect::Launch, Effect::Explosion(2), Effect::Explosion(0), Effect::StarCollected]
+    );
+  }
+
+  #[test]
+  fn volumes_are_mixed_from_master_and_group_volume() {
+    let mixer = Mixer {
+      master: 0.5,
+      effects: 0.8,
+      music: 0.4,
+    };
+    let (mut audio, played) = recording_audio();
+    audio.play_events_mixed(&[GameEvent::GameOver], &mixer);
+    audio.update_music_mixed(0.0, &mixer);
+    audio.update_music_mixed(75.0, &mixer);
+    audio.stop_music();
+    audio.stop_music();
+
+    let expected = [
+      Played::Effect(Effect::GameOver, 0.5 * 0.8 * Effect::GameOver.volume()),
+      // The music starts with all layers silent and fades them in one after another
+      Played::Music(vec![0.0, 0.0, 0.0]),
+      Played::Music(vec![0.5 * 0.4, 0.5 * 0.4 * 0.5, 0.0]),
+      Played::StopMusic,
+    ];
+    assert_eq!(*played.lock().unwrap(), expected);
+  }
+}
@@ -0,0 +1,216 @@
+//! A tiny synthesizer that creates all sounds of the game in memory
+
+use std::f32::consts::TAU;
+
+pub const SAMPLE_RATE: u32 = 22050;
+
+// Length of the music loop: four bars of four beats at 120 beats per minute
+const BEAT: f32 = 0.5;
+const BAR: f32 = 4.0 * BEAT;
+pub const MUSIC_LOOP: f32 = 4.0 * BAR;
+
+// Chords of the four bars (A minor, F, C, G) with the bass note first
+const CHORDS: [[f32; 4]; 4] = [
+  [110.0, 220.0, 261.63, 329.63],
+  [87.31, 174.61, 220.0, 261.63],
+  [130.81, 261.63, 329.63, 392.0],
+  [98.0, 196.0, 246.94, 293.66],
+];
+
+/// White noise with a fixed seed, so the game sounds the same on every start
+struct Noise(u32);
+
+impl Noise {
+  fn new() -> Self {
+    Self(0x1234_5678)
+  }
+
+  fn next(&mut self) -> f32 {
+    // xorshift32
+    self.0 ^= self.0 << 13;
+    self.0 ^= self.0 >> 17;
+    self.0 ^= self.0 << 5;
+    self.0 as f32 / u32::MAX as f32 * 2.0 - 1.0
+  }
+}
+
+/// Simple one pole low pass filter, a smaller `factor` gives a duller sound
+struct LowPass {
+  factor: f32,
+  value: f32,
+}
+
+impl LowPass {
+  fn new(factor: f32) -> Self {
+    Self { factor, value: 0.0 }
+  }
+
+  fn apply(&mut self, sample: f32) -> f32 {
+    self.value += (sample - self.value) * self.factor;
+    self.value
+  }
+}
+
+// Call `sample` with the time of every sample of a sound that is `length` seconds long
+fn render(length: f32, mut sample: impl FnMut(f32) -> f32) -> Vec<f32> {
+  let count = (length * SAMPLE_RATE as f32) as usize;
+  (0..count)
+    .map(|i| sample(i as f32 / SAMPLE_RATE as f32).clamp(-1.0, 1.0))
+    .collect()
+}
+
+// Quick attack followed by a quadratic fade out, `t` and `length` in seconds
+fn envelope(t: f32, length: f32) -> f32 {
+  let attack = (t / 0.005).min(1.0);
+  let decay = (1.0 - t / length).max(0.0);
+  attack * decay * decay
+}
+
+fn sine(t: f32, frequency: f32) -> f32 {
+  (t * frequency * TAU).sin()
+}
+
+fn square(t: f32, frequency: f32) -> f32 {
+  if (t * frequency).fract() < 0.5 { 1.0 } else { -1.0 }
+}
+
+// A short note that starts at `start` and fades out within `length` seconds
+fn note(t: f32, start: f32, length: f32, wave: impl Fn(f32) -> f32) -> f32 {
+  let t = t - start;
+  if (0.0..length).contains(&t) { wave(t) * envelope(t, length) } else { 0.0 }
+}
+
+/// A whoosh that rises in pitch
+pub fn launch() -> Vec<f32> {
+  let length = 0.25;
+  let mut noise = Noise::new();
+  let mut phase = 0.0;
+  render(length, |t| {
+    let frequency = 300.0 + 900.0 * t / length;
+    phase += frequency / SAMPLE_RATE as f32;
+    ((phase * TAU).sin() * 0.4 + noise.next() * 0.2) * envelope(t, length)
+  })
+}
+
+/// A rumbling blast. A `pitch` above 1.0 gives a shorter and brighter explosion, below 1.0 a deeper one.
+pub fn explosion(pitch: f32) -> Vec<f32> {
+  let length = 0.9 / pitch.sqrt();
+  let mut noise = Noise::new();
+  let mut filter = LowPass::new((0.08 * pitch).min(1.0));
+  render(length, |t| {
+    let rumble = sine(t, 50.0 * pitch) * 0.4;
+    (filter.apply(noise.next()) * 2.0 + rumble) * envelope(t, length)
+  })
+}
+
+/// A long crash with a falling tone
+pub fn bunker_destroyed() -> Vec<f32> {
+  let length = 1.4;
+  let mut noise = Noise::new();
+  let mut filter = LowPass::new(0.04);
+  let mut phase = 0.0;
+  render(length, |t| {
+    let frequency = 200.0 - 160.0 * t / length;
+    phase += frequency / SAMPLE_RATE as f32;
+    let tone = if phase.fract() < 0.5 { 0.3 } else { -0.3 };
+    (filter.apply(noise.next()) * 2.5 + tone) * envelope(t, length)
+  })
+}
+
+/// Two bright chimes
+pub fn star_collected() -> Vec<f32> {
+  render(0.35, |t| {
+    note(t, 0.0, 0.2, |t| sine(t, 1046.5)) * 0.5 + note(t, 0.1, 0.25, |t| sine(t, 1568.0)) * 0.5
+  })
+}
+
+/// A rising arpeggio
+pub fn level_up() -> Vec<f32> {
+  let notes = [523.25, 659.25, 783.99, 1046.5];
+  render(0.5, |t| {
+    (notes.iter().enumerate())
+      .map(|(i, &frequency)| note(t, i as f32 * 0.08, 0.2, |t| square(t, frequency)) * 0.2)
+      .sum()
+  })
+}
+
+/// A slow falling melody
+pub fn game_over() -> Vec<f32> {
+  let notes = [392.0, 329.63, 261.63, 196.0];
+  render(1.8, |t| {
+    (notes.iter().enumerate())
+      .map(|(i, &frequency)| {
+        let length = if i == notes.len() - 1 { 0.8 } else { 0.4 };
+        note(t, i as f32 * 0.35, length, |t| sine(t, frequency) * 0.5 + square(t, frequency) * 0.1)
+      })
+      .sum()
+  })
+}
+
+// The chord played at time `t` of the music loop
+fn chord_at(t: f32) -> [f32; 4] {
+  CHORDS[(t / BAR) as usize % CHORDS.len()]
+}
+
+/// Bass line in eighth notes, the calm base of the music
+pub fn music_bass() -> Vec<f32> {
+  render(MUSIC_LOOP, |t| {
+    let eighth = BEAT / 2.0;
+    let bass = chord_at(t)[0];
+    note(t % eighth, 0.0, eighth, |t| sine(t, bass) * 0.6 + square(t, bass) * 0.1)
+  })
+}
+
+/// Kick drum on every beat, snare on the second and fourth beat and hi-hats in between
+pub fn music_drums() -> Vec<f32> {
+  let mut noise = Noise::new();
+  let mut filter = LowPass::new(0.3);
+  render(MUSIC_LOOP, |t| {
+    let noise = noise.next();
+    let high_noise = noise - filter.apply(noise);
+    let beat = (t / BEAT) as usize;
+    let t_in_beat = t % BEAT;
+
+    let kick = note(t_in_beat, 0.0, 0.15, |t| sine(t, 120.0 - 400.0 * t)) * 0.7;
+    let snare = if beat % 2 == 1 { note(t_in_beat, 0.0, 0.12, |_| noise) * 0.3 } else { 0.0 };
+    let hihat = note(t_in_beat, BEAT / 2.0, 0.05, |_| high_noise) * 0.3;
+    kick + snare + hihat
+  })
+}
+
+/// Fast arpeggio over the chords, the most intense layer of the music
+pub fn music_lead() -> Vec<f32> {
+  render(MUSIC_LOOP, |t| {
+    let sixteenth = BEAT / 4.0;
+    let step = (t / sixteenth) as usize;
+    let chord = chord_at(t);
+    let frequency = chord[1 + step % 3] * 2.0;
+    note(t % sixteenth, 0.0, sixteenth, |t| square(t, frequency)) * 0.12
+  })
+}
+
+/// Encode mono samples between -1.0 and 1.0 as a 16 bit WAV file
+pub fn encode_wav(samples: &[f32]) -> Vec<u8> {
+  let data_size = (samples.len() * 2) as u32;
+  let mut wav = Vec::with_capacity(44 + data_size as usize);
+  wav.extend_from_slice(b"RIFF");
+  wav.extend_from_slice(&(36 + data_size).to_le_bytes());
+  wav.extend_from_slice(b"WAVE");
+
+  // Format: PCM, one channel, 16 bits per sample
+  wav.extend_from_slice(b"fmt ");
+  wav.extend_from_slice(&16u32.to_le_bytes());
+  wav.extend_from_slice(&1u16.to_le_bytes());
+  wav.extend_from_slice(&1u16.to_le_bytes());
+  wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+  wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+  wav.extend_from_slice(&2u16.to_le_bytes());
+  wav.extend_from_slice(&16u16.to_le_bytes());
+
+  wav.extend_from_slice(b"data");
+  wav.extend_from_slice(&data_size.to_le_bytes());
+  for sample in samples {
+    wav.extend_from_slice(&((sample * i16::MAX as f32) as i16).to_le_bytes());
+  }
+  wav
+}
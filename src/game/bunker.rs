@@ -1,10 +1,24 @@
 use macroquad::prelude::Vec2;
 
+use crate::game::constants::{BUNKER_MAGAZINE_SIZE, BUNKER_MAX_HEALTH};
+
+/// How badly a bunker is damaged, used to draw it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DamageState {
+  Intact,
+  Cracked,
+  Burning,
+  Destroyed,
+}
+
 #[derive(Clone, Copy)]
 pub struct Bunker {
   pub pos: Vec2,
   pub active: bool,
-  pub firing: bool,
+  pub ammo: u32,
+  pub reload_timer: f32, // Time spent loading the next round
+  pub health: f32,
+  pub rebuild_progress: f32, // How far a destroyed bunker has been rebuilt, from 0.0 to 1.0
 }
 
 impl Bunker {
@@ -12,12 +26,87 @@ impl Bunker {
     Self {
       pos,
       active: true,
-      firing: false,
+      ammo: BUNKER_MAGAZINE_SIZE,
+      reload_timer: 0.0,
+      health: BUNKER_MAX_HEALTH,
+      rebuild_progress: 0.0,
     }
   }
 
+  /// Bring a destroyed bunker back. It starts with an empty magazine.
   pub fn reset(&mut self) {
     self.active = true;
-    self.firing = false;
+    self.ammo = 0;
+    self.reload_timer = 0.0;
+    self.health = BUNKER_MAX_HEALTH;
+    self.rebuild_progress = 0.0;
+  }
+
+  /// Remove `amount` health from the bunker. Returns true if this destroyed the bunker.
+  pub fn apply_damage(&mut self, amount: f32) -> bool {
+    if !self.active {
+      return false;
+    }
+    self.health -= amount;
+    if self.health <= 0.0 {
+      self.health = 0.0;
+      self.active = false;
+      true
+    } else {
+      false
+    }
+  }
+
+  pub fn can_fire(&self) -> bool {
+    self.active && self.ammo > 0
+  }
+
+  /// Use up one round of the magazine. Returns false if the magazine is empty.
+  pub fn take_round(&mut self) -> bool {
+    if self.ammo == 0 {
+      return false;
+    }
+    self.ammo -= 1;
+    true
   }
-}
\ No newline at end of file
+
+  /// Load the magazine one round at a time, each round takes `reload_time` seconds
+  pub fn reload(&mut self, dt: f32, reload_time: f32, magazine_size: u32) {
+    if !self.active || self.ammo >= magazine_size {
+      self.reload_timer = 0.0;
+      return;
+    }
+    self.reload_timer += dt;
+    if self.reload_timer >= reload_time {
+      self.reload_timer -= reload_time;
+      self.ammo += 1;
+    }
+  }
+
+  pub fn damage_state(&self) -> DamageState {
+    let health = self.health / BUNKER_MAX_HEALTH;
+    if !self.active {
+      DamageState::Destroyed
+    } else if health > 2.0 / 3.0 {
+      DamageState::Intact
+    } else if health > 1.0 / 3.0 {
+      DamageState::Cracked
+    } else {
+      DamageState::Burning
+    }
+  }
+
+  /// Continue rebuilding a destroyed bunker. Returns true once the bunker is active again.
+  pub fn rebuild(&mut self, progress: f32) -> bool {
+    if self.active {
+      return false;
+    }
+    self.rebuild_progress += progress;
+    if self.rebuild_progress >= 1.0 {
+      self.reset();
+      true
+    } else {
+      false
+    }
+  }
+}
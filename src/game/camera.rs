@@ -0,0 +1,94 @@
+use macroquad::prelude::{Rect, Vec2};
+
+use crate::game::events::GameEvent;
+use crate::settings::EffectsIntensity;
+
+// Largest offset in view units and rotation in degrees of the shake at full trauma
+const SHAKE_MAX_OFFSET: f32 = 12.0;
+const SHAKE_MAX_ROTATION: f32 = 2.0;
+// How fast the shake wobbles, in cycles per second
+const SHAKE_FREQUENCY: f32 = 18.0;
+// Trauma lost per second
+const TRAUMA_DECAY: f32 = 1.2;
+// Chain reactions with at least this many kills punch the camera
+const PUNCH_CHAIN_KILLS: u32 = 5;
+// Zoom at the peak of a punch and how much of the punch is lost per second
+const PUNCH_ZOOM: f32 = 0.06;
+const PUNCH_DECAY: f32 = 3.0;
+// The camera slowly floats around the center of the view by up to this many view units
+const DRIFT_DISTANCE: f32 = 3.0;
+
+/// Trauma based screen shake, zoom punches and a slow drift of the camera.
+/// They are applied to the view rect the `Viewport` of the game is created from and never touch the simulation.
+pub struct CameraEffects {
+  // From 0.0 to 1.0, the shake grows with the square of it
+  trauma: f32,
+  // From 0.0 to 1.0
+  punch: f32,
+  time: f32,
+  // Scales all effects, 0.0 turns them off
+  strength: f32,
+}
+
+impl CameraEffects {
+  pub fn new(intensity: EffectsIntensity) -> Self {
+    let mut camera = Self {
+      trauma: 0.0,
+      punch: 0.0,
+      time: 0.0,
+      strength: 0.0,
+    };
+    camera.set_intensity(intensity);
+    camera
+  }
+
+  pub fn set_intensity(&mut self, intensity: EffectsIntensity) {
+    self.strength = match intensity {
+      EffectsIntensity::Off => 0.0,
+      EffectsIntensity::Low => 0.5,
+      EffectsIntensity::Normal | EffectsIntensity::High => 1.0,
+    };
+  }
+
+  pub fn add_trauma(&mut self, amount: f32) {
+    self.trauma = (self.trauma + amount).min(1.0);
+  }
+
+  pub fn handle_events(&mut self, events: &[GameEvent]) {
+    for event in events {
+      match *event {
+        GameEvent::BunkerDestroyed { .. } => self.add_trauma(0.6),
+        GameEvent::GameOver => self.add_trauma(1.0),
+        GameEvent::MissileIntercepted { chain_kills, .. } if chain_kills >= PUNCH_CHAIN_KILLS => self.punch = 1.0,
+        _ => {}
+      }
+    }
+  }
+
+  pub fn update(&mut self, dt: f32) {
+    self.time += dt;
+    self.trauma = (self.trauma - TRAUMA_DECAY * dt).max(0.0);
+    self.punch = (self.punch - PUNCH_DECAY * dt).max(0.0);
+  }
+
+  // Smooth wobble between -1.0 and 1.0, different for every `seed`
+  fn wobble(&self, frequency: f32, seed: f32) -> f32 {
+    let t = self.time * frequency * std::f32::consts::TAU;
+    ((t + seed).sin() + (t * 1.7 + seed * 3.1).sin()) / 2.0
+  }
+
+  /// The view rect and rotation in degrees with all effects applied to `view_rect`
+  pub fn apply(&self, view_rect: Rect) -> (Rect, f32) {
+    let shake = self.trauma * self.trauma * self.strength;
+    let shake_offset = Vec2::new(self.wobble(SHAKE_FREQUENCY, 0.0), self.wobble(SHAKE_FREQUENCY, 5.0)) * SHAKE_MAX_OFFSET;
+    let drift = Vec2::new(self.wobble(0.05, 1.0), self.wobble(0.04, 2.0)) * DRIFT_DISTANCE * self.strength;
+    let center = view_rect.center() + shake_offset * shake + drift;
+
+    // Zooming in makes the visible part of the view smaller
+    let zoom = 1.0 + PUNCH_ZOOM * self.punch * self.strength;
+    let size = view_rect.size() / zoom;
+
+    let rotation = self.wobble(SHAKE_FREQUENCY, 9.0) * SHAKE_MAX_ROTATION * shake;
+    (Rect::new(center.x - size.x / 2.0, center.y - size.y / 2.0, size.x, size.y), rotation)
+  }
+}
@@ -0,0 +1,105 @@
+use macroquad::prelude::Vec2;
+
+use crate::game::missile::EnemyKind;
+
+/// The different types of carriers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CarrierKind {
+  /// Slow plane that drops standard missiles
+  Bomber,
+  /// Fast and high flying, drops darts
+  Satellite,
+}
+
+impl CarrierKind {
+  pub fn speed(self) -> f32 {
+    match self {
+      CarrierKind::Bomber => 50.0,
+      CarrierKind::Satellite => 80.0,
+    }
+  }
+
+  /// Range of heights the carrier crosses the sky at
+  pub fn altitude_range(self) -> std::ops::Range<f32> {
+    match self {
+      CarrierKind::Bomber => -200.0..-120.0,
+      CarrierKind::Satellite => -270.0..-230.0,
+    }
+  }
+
+  /// Seconds between two dropped missiles
+  pub fn drop_interval(self) -> f32 {
+    match self {
+      CarrierKind::Bomber => 2.5,
+      CarrierKind::Satellite => 4.0,
+    }
+  }
+
+  pub fn dropped_missile(self) -> EnemyKind {
+    match self {
+      CarrierKind::Bomber => EnemyKind::Standard,
+      CarrierKind::Satellite => EnemyKind::Dart,
+    }
+  }
+
+  /// Experience for shooting the carrier down
+  pub fn bonus_experience(self) -> f32 {
+    match self {
+      CarrierKind::Bomber => 60.0,
+      CarrierKind::Satellite => 100.0,
+    }
+  }
+
+  pub fn radius(self) -> f32 {
+    match self {
+      CarrierKind::Bomber => 12.0,
+      CarrierKind::Satellite => 8.0,
+    }
+  }
+}
+
+/// An enemy that crosses the sky horizontally and drops missiles along its path
+pub struct Carrier {
+  pub kind: CarrierKind,
+  pub pos: Vec2,
+  pub prev_pos: Vec2, // Position before the last simulation tick, used for interpolation
+  pub velocity: Vec2,
+  pub time_until_next_drop: f32,
+  pub destroyed: bool,
+}
+
+impl Carrier {
+  /// Create a carrier that enters the sky at `pos` and flies left or right
+  pub fn new(kind: CarrierKind, pos: Vec2, moving_right: bool) -> Self {
+    let direction = if moving_right { 1.0 } else { -1.0 };
+    Self {
+      kind,
+      pos,
+      prev_pos: pos,
+      velocity: Vec2::new(direction * kind.speed(), 0.0),
+      time_until_next_drop: kind.drop_interval() / 2.0,
+      destroyed: false,
+    }
+  }
+
+  /// Move the carrier. Returns true if it is time to drop a missile.
+  pub fn advance(&mut self, dt: f32) -> bool {
+    self.pos += self.velocity * dt;
+    self.time_until_next_drop -= dt;
+    if self.time_until_next_drop <= 0.0 {
+      self.time_until_next_drop += self.kind.drop_interval();
+      true
+    } else {
+      false
+    }
+  }
+
+  pub fn is_hit_by_explosion(&self, explosion_pos: Vec2, explosion_radius: f32) -> bool {
+    !self.destroyed && self.pos.distance(explosion_pos) <= explosion_radius + self.kind.radius()
+  }
+
+  /// Position between the previous and the current tick
+  pub fn interpolated_pos(&self, alpha: f32) -> Vec2 {
+    self.prev_pos.lerp(self.pos, alpha)
+  }
+}
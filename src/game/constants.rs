@@ -1,32 +1,52 @@
+pub const GROUND_LEVEL: f32 = 280.0; // y of the surface the bunkers stand on
 pub const GROUND_HEIGHT: f32 = 20.0;
 pub const BUNKER_WIDTH: f32 = 50.0;
 pub const BUNKER_HEIGHT: f32 = 20.0;
+pub const BUNKER_MAGAZINE_SIZE: u32 = 3;
+pub const BUNKER_RELOAD_TIME: f32 = 1.5; // seconds to load a single round
+pub const BUNKER_MAX_HEALTH: f32 = 100.0;
+pub const BUNKER_MAX_ARMOUR: f32 = 0.8; // armour can never block more than this share of the damage
+pub const ENEMY_MISSILE_DAMAGE: f32 = 50.0; // damage of a direct hit on the targeted bunker
+pub const EXPLOSION_SPLASH_DAMAGE: f32 = 20.0; // damage of enemy explosions to bunkers within reach
+pub const BUNKER_REBUILD_TIME: f32 = 60.0; // seconds until a destroyed bunker is rebuilt on its own
+pub const BUNKER_REBUILD_COST: f32 = 50.0; // experience to rebuild a destroyed bunker instantly
 pub const MISSILE_SPEED: f32 = 100.0;
 pub const EXPLOSION_MAX_RADIUS: f32 = 50.0;
 pub const EXPLOSION_GROWTH_RATE: f32 = 200.0; // pixel/sec
 pub const EXPLOSION_AFTER_GLOW: f32 = 0.1;
-pub const ENEMY_MISSILE_SPAWN_INTERVAL: f32 = 4.0;
+pub const WARHEAD_SPLIT_DISTANCE: f32 = 60.0; // distance to the target at which split missiles release their warheads
+pub const WARHEAD_SPREAD: f32 = 35.0; // distance of the warhead targets from the original target
+pub const WARHEAD_EXPLOSION_SCALE: f32 = 0.6;
+pub const MIRV_FORK_Y: f32 = -50.0; // MIRVs fork into their warheads when they get below this height
+pub const MIRV_WARHEADS: u32 = 3;
+pub const EVASIVE_LEG_LENGTH: f32 = 90.0; // distance evasive missiles fly towards the target before they change heading
+pub const EVASIVE_SWERVE: f32 = 70.0; // how far evasive missiles swerve to the side on every leg
+pub const EVASIVE_FINAL_APPROACH: f32 = 150.0; // evasive missiles fly straight once they are this close to the target
+pub const SMART_BOMB_LOOKAHEAD: f32 = 120.0; // distance at which smart bombs start to dodge explosions
+pub const SMART_BOMB_MARGIN: f32 = 10.0; // distance smart bombs try to keep from the edge of an explosion
+pub const SMART_BOMB_COMMIT_DISTANCE: f32 = 40.0; // smart bombs stop dodging this close to their target
+pub const SMART_BOMB_MIN_DESCENT: f32 = 0.2; // smallest downward component of the heading of a smart bomb
+pub const MISSILE_TRAIL_MAX_POINTS: usize = 64; // longer trails are thinned out, so steering missiles don't grow them forever
+pub const CARRIER_FIRST_SPAWN_TIME: f32 = 45.0; // seconds until the first carrier shows up
+pub const CARRIER_SKY_EDGE: f32 = 440.0; // carriers enter and leave the sky at this distance from the center
+pub const CARRIER_DROP_EDGE: f32 = 380.0; // carriers only drop missiles while they are closer to the center than this
+pub const WAVE_BREAK_TIME: f32 = 6.0; // seconds between two waves
+pub const WAVE_BUNKER_BONUS: f32 = 40.0; // experience for every bunker that survived a wave
+pub const WAVE_AMMO_BONUS: f32 = 5.0; // experience for every missile left in the magazines after a wave
+pub const WAVE_SPAWN_INTERVAL_SCALE: f32 = 0.6; // enemies arrive closer together in waves than in endless mode
+pub const WAVE_CARRIER_EVERY: u32 = 3; // every n-th wave brings a carrier
 pub const INITIAL_SPAWN_TIME: f32 = 2.0; // seconds
+pub const SIMULATION_TICK_RATE: f32 = 120.0; // ticks/sec
+pub const SIMULATION_DT: f32 = 1.0 / SIMULATION_TICK_RATE;
+pub const MAX_FRAME_TIME: f32 = 0.25; // longer frames are clamped, so a hitch can't trigger an avalanche of ticks
+pub const PAUSE_FRAME_TIME: f32 = 0.5; // frames this long mean the game stalled or its browser tab was hidden, so it pauses
 
-// Calculate enemy missile spawn interval based on elapsed time
-pub fn get_enemy_missile_spawn_interval(elapsed_time: f32) -> f32 {
-  // Start with a long interval and decrease over time
-  // After 5 minutes, the interval will be 1/3 of the initial interval
-  let min_interval = ENEMY_MISSILE_SPAWN_INTERVAL / 5.0;
-  let time_to_min_interval = 300.0; // 5 minutes in seconds
-
-  let interval_multiplier = 1.0 - (elapsed_time / time_to_min_interval) * (1.0 - min_interval / ENEMY_MISSILE_SPAWN_INTERVAL);
-  (ENEMY_MISSILE_SPAWN_INTERVAL * interval_multiplier).max(0.1)
+// Number of enemy missiles that spawn in a wave
+pub fn get_wave_missile_budget(wave: u32) -> u32 {
+  6 + 4 * wave
 }
 
-// Calculate enemy missile speed based on elapsed time
-pub fn get_enemy_missile_speed(elapsed_time: f32) -> f32 {
-  // Start with a slow speed and increase over time
-  // After 5 minutes, the speed will be 3 times the initial speed
-  let base_speed = MISSILE_SPEED * 0.5; // Start at half the player missile speed
-  let max_speed_multiplier = 5.0;
-  let time_to_max_speed = 300.0; // 5 minutes in seconds
-
-  let speed_multiplier = 1.0 + (elapsed_time / time_to_max_speed) * (max_speed_multiplier - 1.0);
-  base_speed * speed_multiplier.min(max_speed_multiplier)
+// Every wave is as hard as the endless mode after this many seconds
+pub fn get_wave_difficulty_time(wave: u32) -> f32 {
+  (wave - 1) as f32 * 25.0
 }
@@ -0,0 +1,237 @@
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::game::missile::EnemyKind;
+
+lazy_static! {
+  static ref DIFFICULTY_BOOK: DifficultyBook =
+    DifficultyBook::parse(include_str!("../../assets/difficulty.ron")).unwrap_or_else(|err| panic!("{err}"));
+}
+
+/// Difficulty presets the player can choose from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Difficulty {
+  Easy,
+  #[default]
+  Normal,
+  Hard,
+  Insane,
+}
+
+impl Difficulty {
+  pub const ALL: [Difficulty; 4] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard, Difficulty::Insane];
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Difficulty::Easy => "Easy",
+      Difficulty::Normal => "Normal",
+      Difficulty::Hard => "Hard",
+      Difficulty::Insane => "Insane",
+    }
+  }
+
+  pub fn next(self) -> Self {
+    let index = Self::ALL.iter().position(|&difficulty| difficulty == self).unwrap_or(0);
+    Self::ALL[(index + 1) % Self::ALL.len()]
+  }
+
+  /// Name of the difficulty in replay files and launch parameters
+  pub fn id(self) -> &'static str {
+    match self {
+      Difficulty::Easy => "easy",
+      Difficulty::Normal => "normal",
+      Difficulty::Hard => "hard",
+      Difficulty::Insane => "insane",
+    }
+  }
+
+  pub fn from_id(id: &str) -> Option<Self> {
+    Self::ALL.into_iter().find(|difficulty| difficulty.id() == id)
+  }
+
+  /// How the game gets harder over time with this preset
+  pub fn curves(self) -> &'static DifficultyCurves {
+    DifficultyBook::get().curves(self)
+  }
+}
+
+/// A value that changes over time, given as `(time, value)` keyframes.
+/// Between two keyframes the value is interpolated linearly, before the first
+/// and after the last keyframe it stays at the value of that keyframe.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(transparent)]
+pub struct Curve(Vec<(f32, f32)>);
+
+impl Curve {
+  pub fn sample(&self, time: f32) -> f32 {
+    let keyframes = &self.0;
+    let next = keyframes.iter().position(|&(keyframe_time, _)| keyframe_time > time);
+    match next {
+      Some(0) => keyframes[0].1,
+      Some(next) => {
+        let (start_time, start_value) = keyframes[next - 1];
+        let (end_time, end_value) = keyframes[next];
+        let progress = (time - start_time) / (end_time - start_time);
+        start_value + (end_value - start_value) * progress
+      }
+      None => keyframes.last().map_or(0.0, |&(_, value)| value),
+    }
+  }
+
+  fn validate(&self, name: &str) -> Result<(), String> {
+    if self.0.is_empty() {
+      return Err(format!("Curve '{name}' has no keyframes"));
+    }
+    if self.0.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+      return Err(format!("Keyframes of curve '{name}' are not in order"));
+    }
+    Ok(())
+  }
+}
+
+/// Spawn weights of the enemy kinds over time
+#[derive(Debug, Clone, Deserialize)]
+pub struct EnemyMix {
+  pub standard: Curve,
+  pub dart: Curve,
+  pub evasive: Curve,
+  pub mirv: Curve,
+  pub smart_bomb: Curve,
+}
+
+/// Definition of a difficulty preset as written in `assets/difficulty.ron`.
+/// All curves are sampled with the seconds since the start of the run.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DifficultyCurves {
+  pub difficulty: Difficulty,
+  /// Average seconds between two enemy missiles
+  pub spawn_interval: Curve,
+  pub missile_speed: Curve,
+  pub enemy_mix: EnemyMix,
+  /// Average seconds between two carriers
+  pub carrier_interval: Curve,
+  /// Chance of a carrier to be a satellite instead of a bomber
+  pub satellite_chance: Curve,
+}
+
+impl DifficultyCurves {
+  /// How likely each kind of enemy missile is to spawn at the given time
+  pub fn enemy_weights(&self, time: f32) -> [(EnemyKind, f32); 5] {
+    let mix = &self.enemy_mix;
+    [
+      (EnemyKind::Standard, mix.standard.sample(time)),
+      (EnemyKind::Dart, mix.dart.sample(time)),
+      (EnemyKind::Evasive, mix.evasive.sample(time)),
+      (EnemyKind::Mirv, mix.mirv.sample(time)),
+      (EnemyKind::SmartBomb, mix.smart_bomb.sample(time)),
+    ]
+  }
+
+  fn validate(&self) -> Result<(), String> {
+    let curves = [
+      ("spawn_interval", &self.spawn_interval),
+      ("missile_speed", &self.missile_speed),
+      ("enemy_mix.standard", &self.enemy_mix.standard),
+      ("enemy_mix.dart", &self.enemy_mix.dart),
+      ("enemy_mix.evasive", &self.enemy_mix.evasive),
+      ("enemy_mix.mirv", &self.enemy_mix.mirv),
+      ("enemy_mix.smart_bomb", &self.enemy_mix.smart_bomb),
+      ("carrier_interval", &self.carrier_interval),
+      ("satellite_chance", &self.satellite_chance),
+    ];
+    for (name, curve) in curves {
+      curve
+        .validate(name)
+        .map_err(|err| format!("{err} of difficulty {}", self.difficulty.name()))?;
+    }
+    if self.enemy_mix.standard.0.iter().any(|&(_, weight)| weight <= 0.0) {
+      return Err(format!("Standard missiles must always spawn on difficulty {}", self.difficulty.name()));
+    }
+    // Negative weights could make the total weight zero, so no kind could be picked
+    let mix = &self.enemy_mix;
+    for (name, curve) in [("dart", &mix.dart), ("evasive", &mix.evasive), ("mirv", &mix.mirv), ("smart_bomb", &mix.smart_bomb)] {
+      if curve.0.iter().any(|&(_, weight)| weight < 0.0) {
+        return Err(format!("Curve 'enemy_mix.{name}' of difficulty {} has negative weights", self.difficulty.name()));
+      }
+    }
+    for (name, curve) in [("spawn_interval", &self.spawn_interval), ("carrier_interval", &self.carrier_interval)] {
+      if curve.0.iter().any(|&(_, interval)| interval <= 0.0) {
+        return Err(format!("Curve '{name}' of difficulty {} must stay above zero", self.difficulty.name()));
+      }
+    }
+    Ok(())
+  }
+}
+
+/// The curves of all difficulty presets
+pub struct DifficultyBook {
+  curves: Vec<DifficultyCurves>,
+}
+
+impl DifficultyBook {
+  pub fn get() -> &'static DifficultyBook {
+    &DIFFICULTY_BOOK
+  }
+
+  pub fn parse(text: &str) -> Result<Self, String> {
+    let curves: Vec<DifficultyCurves> =
+      ron::from_str(text).map_err(|err| format!("Invalid difficulty definitions: {err}"))?;
+
+    for difficulty in Difficulty::ALL {
+      match curves.iter().filter(|curves| curves.difficulty == difficulty).count() {
+        0 => return Err(format!("Difficulty {} is not defined", difficulty.name())),
+        1 => {}
+        _ => return Err(format!("Difficulty {} is defined more than once", difficulty.name())),
+      }
+    }
+    for curves in &curves {
+      curves.validate()?;
+    }
+
+    Ok(Self { curves })
+  }
+
+  pub fn curves(&self, difficulty: Difficulty) -> &DifficultyCurves {
+    // Every difficulty is defined exactly once, this is checked when parsing
+    self.curves.iter().find(|curves| curves.difficulty == difficulty).unwrap()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const DEFINITIONS: &str = include_str!("../../assets/difficulty.ron");
+
+  #[test]
+  fn shipped_definitions_are_valid() {
+    DifficultyBook::parse(DEFINITIONS).unwrap();
+  }
+
+  #[test]
+  fn negative_enemy_weights_are_rejected() {
+    let broken = DEFINITIONS.replacen("dart: [(60.0, 0.0), (150.0, 0.3)]", "dart: [(60.0, 0.0), (150.0, -5.0)]", 1);
+    assert_ne!(broken, DEFINITIONS);
+    let err = DifficultyBook::parse(&broken).err().unwrap();
+    assert!(err.contains("enemy_mix.dart"), "{err}");
+  }
+
+  #[test]
+  fn intervals_must_stay_above_zero() {
+    let broken = DEFINITIONS.replacen("spawn_interval: [(0.0, 5.0)", "spawn_interval: [(0.0, 0.0)", 1);
+    assert_ne!(broken, DEFINITIONS);
+    assert!(DifficultyBook::parse(&broken).is_err());
+
+    let broken = DEFINITIONS.replacen("carrier_interval: [(0.0, 40.0)", "carrier_interval: [(0.0, -1.0)", 1);
+    assert_ne!(broken, DEFINITIONS);
+    assert!(DifficultyBook::parse(&broken).is_err());
+  }
+
+  #[test]
+  fn curves_interpolate_between_keyframes() {
+    let curve = Curve(vec![(10.0, 1.0), (20.0, 3.0)]);
+    assert_eq!(curve.sample(0.0), 1.0);
+    assert_eq!(curve.sample(15.0), 2.0);
+    assert_eq!(curve.sample(30.0), 3.0);
+  }
+}
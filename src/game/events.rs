@@ -0,0 +1,18 @@
+use macroquad::prelude::Vec2;
+
+/// Something noteworthy that happened during a step of the world.
+/// The front-end turns these into sounds and effects, the simulation itself never reads them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameEvent {
+  /// The player launched a missile from a bunker
+  MissileLaunched { pos: Vec2 },
+  /// An explosion started, `max_radius` is the size it will grow to
+  Explosion { pos: Vec2, max_radius: f32, hostile: bool },
+  /// An explosion destroyed an enemy missile. `chain_kills` counts the kills of its chain reaction so far.
+  MissileIntercepted { pos: Vec2, chain_kills: u32 },
+  BunkerDestroyed { bunker_idx: usize, pos: Vec2 },
+  StarCollected { pos: Vec2 },
+  /// The player picked a skill
+  LevelUp,
+  GameOver,
+}
@@ -36,9 +36,21 @@ impl From<&Player> for ExplosionParams {
     }
 }
 
+impl ExplosionParams {
+    /// Parameters for a smaller (or bigger) explosion that takes the same time to expand
+    pub fn scaled(self, scale: f32) -> Self {
+        Self {
+            max_radius: self.max_radius * scale,
+            growth_rate: self.growth_rate * scale,
+            static_duration: self.static_duration,
+        }
+    }
+}
+
 pub struct Explosion {
   pub pos: Vec2,
   pub radius: f32,
+  pub prev_radius: f32, // Radius before the last simulation tick, used for interpolation
   pub max_radius: f32,
   pub phase: ExplosionPhase,
   pub phase_transition_radius: f32, // 80% of max_radius
@@ -46,6 +58,9 @@ pub struct Explosion {
   pub current_growth_rate: f32,   // The current growth rate (changes in phase 2)
   pub static_duration: f32,       // How long the explosion stays at max radius
   pub static_time_elapsed: f32,   // Time elapsed in the static phase
+  pub chain: usize,               // Chain reaction this explosion belongs to
+  pub hostile: bool,              // Explosions of enemy missiles damage bunkers
+  pub damaged_bunkers: u32,       // Bit mask of the bunkers this explosion already damaged
 }
 
 impl Explosion {
@@ -58,6 +73,7 @@ impl Explosion {
     Explosion {
       pos,
       radius: 0.0,
+      prev_radius: 0.0,
       max_radius,
       phase: ExplosionPhase::Linear,
       phase_transition_radius,
@@ -65,6 +81,9 @@ impl Explosion {
       current_growth_rate: initial_growth_rate,
       static_duration,
       static_time_elapsed: 0.0,
+      chain: 0,
+      hostile: false,
+      damaged_bunkers: 0,
     }
   }
 
@@ -73,7 +92,30 @@ impl Explosion {
     Self::new(pos, ExplosionParams::default())
   }
 
+  // Mark the explosion as part of the given chain reaction
+  pub fn in_chain(mut self, chain: usize) -> Explosion {
+    self.chain = chain;
+    self
+  }
+
+  // Mark the explosion as coming from an enemy missile, so it damages bunkers in reach
+  pub fn hostile(mut self) -> Explosion {
+    self.hostile = true;
+    self
+  }
+
+  // Remember that the explosion damaged the given bunker.
+  // Returns false if it already did, so every explosion damages a bunker at most once.
+  pub fn mark_bunker_damaged(&mut self, bunker_idx: usize) -> bool {
+    let bit = 1 << bunker_idx;
+    let first_time = self.damaged_bunkers & bit == 0;
+    self.damaged_bunkers |= bit;
+    first_time
+  }
+
   pub fn update(&mut self, dt: f32) {
+    self.prev_radius = self.radius;
+
     match self.phase {
       // Phase 1: Linear growth until 80% of max radius
       ExplosionPhase::Linear => {
@@ -144,6 +186,11 @@ impl Explosion {
     }
   }
 
+  // Radius between the previous and the current tick
+  pub fn interpolated_radius(&self, alpha: f32) -> f32 {
+    self.prev_radius + (self.radius - self.prev_radius) * alpha
+  }
+
   // Check if the explosion has completed all phases
   pub fn has_ended(&self) -> bool {
     // Only end if we're in the Static phase and have stayed there for the required duration
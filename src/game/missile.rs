@@ -1,30 +1,322 @@
 use macroquad::prelude::Vec2;
-use crate::game::constants::{MISSILE_SPEED, get_enemy_missile_speed};
+
+use crate::game::constants::{
+  MISSILE_TRAIL_MAX_POINTS, SMART_BOMB_COMMIT_DISTANCE, SMART_BOMB_LOOKAHEAD, SMART_BOMB_MARGIN, SMART_BOMB_MIN_DESCENT,
+};
+use crate::game::explosion::Explosion;
+
+/// How a missile behaves on its way to the target
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MissileBehaviour {
+  /// Fly straight to the target and explode there
+  Direct,
+  /// Fly towards the target and split into several warheads shortly before reaching it
+  Split { warheads: u32 },
+}
+
+/// The different types of enemy missiles
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnemyKind {
+  /// Flies straight at a bunker
+  Standard,
+  /// Forks into several standard missiles aimed at different bunkers mid-flight
+  Mirv,
+  /// Changes its heading every now and then on the way down
+  Evasive,
+  /// Much faster than the others, and just as fragile as a standard missile
+  Dart,
+  /// Steers around explosions in its way
+  SmartBomb,
+}
+
+impl EnemyKind {
+  pub fn speed_multiplier(self) -> f32 {
+    match self {
+      EnemyKind::Standard => 1.0,
+      EnemyKind::Mirv => 0.8,
+      EnemyKind::Evasive => 0.9,
+      EnemyKind::Dart => 2.0,
+      EnemyKind::SmartBomb => 0.8,
+    }
+  }
+
+  pub fn experience_multiplier(self) -> f32 {
+    match self {
+      EnemyKind::Standard => 1.0,
+      EnemyKind::Mirv => 2.0,
+      EnemyKind::Evasive => 1.5,
+      EnemyKind::Dart => 1.5,
+      EnemyKind::SmartBomb => 2.5,
+    }
+  }
+
+  /// Seconds the missile survives inside explosions, missiles without health are destroyed on contact
+  pub fn health(self) -> f32 {
+    match self {
+      EnemyKind::Standard | EnemyKind::Dart | EnemyKind::SmartBomb => 0.0,
+      EnemyKind::Mirv => 0.2,
+      EnemyKind::Evasive => 0.1,
+    }
+  }
+}
+
+/// Who fired a missile
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MissileKind {
+  Player,
+  Enemy { enemy: EnemyKind, target_bunker_idx: usize },
+}
 
 pub struct Missile {
   pub start_pos: Vec2,
   pub target_pos: Vec2,
   pub current_pos: Vec2,
+  pub prev_pos: Vec2, // Position before the last simulation tick, used for interpolation
   pub direction: Vec2,
-  pub target_bunker_idx: Option<usize>,
+  pub kind: MissileKind,
   pub exploded: bool,
   pub speed: f32,
+  pub health: f32, // Seconds the missile survives inside explosions
+  pub behaviour: MissileBehaviour,
+  pub explosion_scale: f32, // Size of the explosion relative to a regular one
+  pub chain: Option<usize>, // Chain reaction the explosion of this missile belongs to, if already known
+  pub waypoint: Option<Vec2>, // Point the missile flies to before heading for the target again
+  pub trail: Vec<Vec2>,       // Points where the missile changed its heading, starting with start_pos
 }
 
 impl Missile {
-  pub fn new(start_pos: Vec2, target_pos: Vec2, target_bunker_idx: Option<usize>, speed: f32) -> Self {
+  pub fn new(start_pos: Vec2, target_pos: Vec2, kind: MissileKind, speed: f32) -> Self {
+    let health = match kind {
+      MissileKind::Player => 0.0,
+      MissileKind::Enemy { enemy, .. } => enemy.health(),
+    };
+
     Self {
       start_pos,
       target_pos,
       current_pos: start_pos,
+      prev_pos: start_pos,
       direction: (target_pos - start_pos).normalize(),
-      target_bunker_idx,
+      kind,
       exploded: false,
       speed,
+      health,
+      behaviour: MissileBehaviour::Direct,
+      explosion_scale: 1.0,
+      chain: None,
+      waypoint: None,
+      trail: vec![start_pos],
     }
   }
-  
+
+  pub fn with_behaviour(mut self, behaviour: MissileBehaviour) -> Self {
+    self.behaviour = behaviour;
+    self
+  }
+
+  pub fn with_explosion_scale(mut self, explosion_scale: f32) -> Self {
+    self.explosion_scale = explosion_scale;
+    self
+  }
+
+  pub fn in_chain(mut self, chain: usize) -> Self {
+    self.chain = Some(chain);
+    self
+  }
+
   pub fn is_player_missile(&self) -> bool {
-    self.target_bunker_idx.is_none()
+    self.kind == MissileKind::Player
+  }
+
+  pub fn enemy_kind(&self) -> Option<EnemyKind> {
+    match self.kind {
+      MissileKind::Player => None,
+      MissileKind::Enemy { enemy, .. } => Some(enemy),
+    }
+  }
+
+  /// Let the missile fly to `waypoint` before it heads for the target again
+  pub fn turn_towards(&mut self, waypoint: Vec2) {
+    self.add_trail_point(self.current_pos);
+    self.waypoint = Some(waypoint);
+    self.direction = (waypoint - self.current_pos).normalize_or_zero();
+  }
+
+  /// Fly in the given direction from now on
+  pub fn steer(&mut self, direction: Vec2) {
+    // Start a new trail segment when the heading changed noticeably
+    let segment = self.current_pos - self.trail.last().copied().unwrap_or(self.start_pos);
+    if segment.length() > 1.0 && segment.normalize().dot(direction) < 0.999 {
+      self.add_trail_point(self.current_pos);
+    }
+    self.direction = direction;
+  }
+
+  // Drop every other point once the trail gets too long. It keeps its start and shape, just with fewer corners.
+  fn add_trail_point(&mut self, point: Vec2) {
+    if self.trail.len() >= MISSILE_TRAIL_MAX_POINTS {
+      let mut index = 0;
+      self.trail.retain(|_| {
+        index += 1;
+        index % 2 == 1
+      });
+    }
+    self.trail.push(point);
+  }
+
+  /// Damage the missile for `dt` seconds spent inside an explosion. Returns true if it was destroyed.
+  pub fn take_hit(&mut self, dt: f32) -> bool {
+    self.health -= dt;
+    self.health <= 0.0
+  }
+
+  /// Move the missile towards its target. Returns true if the target was reached.
+  /// The missile never moves past its target, so it can't tunnel through it on long steps.
+  pub fn advance(&mut self, dt: f32) -> bool {
+    let step = self.speed * dt;
+
+    if let Some(waypoint) = self.waypoint {
+      if step < self.current_pos.distance(waypoint) {
+        self.current_pos += self.direction * step;
+        return false;
+      }
+      // Head for the target again from the waypoint
+      self.current_pos = waypoint;
+      self.waypoint = None;
+      self.add_trail_point(waypoint);
+      self.direction = (self.target_pos - waypoint).normalize_or_zero();
+      return false;
+    }
+
+    let remaining = self.current_pos.distance(self.target_pos);
+    if step >= remaining {
+      self.current_pos = self.target_pos;
+      true
+    } else {
+      self.current_pos += self.direction * step;
+      false
+    }
+  }
+
+  /// Where the missile will be after `time` seconds if it keeps flying to its waypoint and target.
+  /// Evasive missiles and smart bombs may change their course before that.
+  pub fn predicted_pos(&self, time: f32) -> Vec2 {
+    let mut pos = self.current_pos;
+    let mut distance = self.speed * time;
+    for stop in self.waypoint.into_iter().chain([self.target_pos]) {
+      let leg = pos.distance(stop);
+      if distance < leg {
+        return pos + (stop - pos).normalize_or_zero() * distance;
+      }
+      distance -= leg;
+      pos = stop;
+    }
+    pos
+  }
+
+  /// Position between the previous and the current tick
+  pub fn interpolated_pos(&self, alpha: f32) -> Vec2 {
+    self.prev_pos.lerp(self.current_pos, alpha)
+  }
+}
+
+/// Heading of a smart bomb at `pos` that flies to `target` and dodges the `explosions` ahead of it.
+/// The result only depends on the arguments, so the steering is the same in every replay.
+pub fn smart_bomb_heading(pos: Vec2, target: Vec2, explosions: &[Explosion]) -> Vec2 {
+  let desired = (target - pos).normalize_or_zero();
+  let mut heading = desired;
+
+  // Commit to the dive once the bomb is close to the target or down at its level
+  if pos.distance(target) < SMART_BOMB_COMMIT_DISTANCE || pos.y >= target.y {
+    return desired;
+  }
+
+  for explosion in explosions {
+    // Explosions keep growing, so dodge their final size
+    let danger_radius = explosion.max_radius + SMART_BOMB_MARGIN;
+    let offset = pos - explosion.pos;
+    let distance = offset.length();
+    let is_behind = offset.dot(desired) > 0.0;
+    if distance > danger_radius + SMART_BOMB_LOOKAHEAD || (is_behind && distance > danger_radius) {
+      continue;
+    }
+
+    // Steer sideways around the explosion, the closer it is the harder
+    let closeness = 1.0 - (distance - danger_radius).max(0.0) / SMART_BOMB_LOOKAHEAD;
+    let side = desired.perp();
+    let away = if side.dot(offset) >= 0.0 { side } else { -side };
+    heading += away * closeness * 2.0;
+  }
+
+  // Keep coming down, so the bomb can be boxed in but never flees upwards
+  let heading = heading.normalize_or(desired);
+  let min_descent = SMART_BOMB_MIN_DESCENT.min(desired.y);
+  if heading.y >= min_descent {
+    return heading;
+  }
+  // Turn down to the minimum descent on the side the bomb was dodging to, still at full speed
+  Vec2::new(heading.x.signum() * (1.0 - min_descent * min_descent).sqrt(), min_descent)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const TARGET: Vec2 = Vec2::new(0.0, 500.0);
+
+  #[test]
+  fn smart_bombs_steer_around_explosions_in_their_path() {
+    let pos = Vec2::ZERO;
+    let explosion = Explosion::new_default(Vec2::new(5.0, 100.0));
+    let heading = smart_bomb_heading(pos, TARGET, &[explosion]);
+    // The explosion is slightly to the right, so the bomb passes it on the left
+    assert!(heading.x < -0.5, "{heading}");
+    assert!(heading.y > 0.0, "{heading}");
+  }
+
+  #[test]
+  fn smart_bombs_ignore_explosions_behind_them() {
+    let pos = Vec2::new(0.0, 200.0);
+    let explosion = Explosion::new_default(Vec2::new(20.0, 100.0));
+    let heading = smart_bomb_heading(pos, TARGET, &[explosion]);
+    assert_eq!(heading, Vec2::Y);
+  }
+
+  #[test]
+  fn smart_bombs_dive_straight_at_a_close_target() {
+    let pos = TARGET - Vec2::new(10.0, SMART_BOMB_COMMIT_DISTANCE - 15.0);
+    let explosion = Explosion::new_default(TARGET - Vec2::new(5.0, 10.0));
+    let heading = smart_bomb_heading(pos, TARGET, &[explosion]);
+    assert_eq!(heading, (TARGET - pos).normalize());
+  }
+
+  #[test]
+  fn trails_of_steering_missiles_stay_short() {
+    let kind = MissileKind::Enemy { enemy: EnemyKind::SmartBomb, target_bunker_idx: 0 };
+    let mut missile = Missile::new(Vec2::ZERO, TARGET, kind, 100.0);
+    for i in 0..1000 {
+      missile.steer(Vec2::from_angle(1.0 + (i % 2) as f32 * 0.5));
+      missile.advance(0.1);
+    }
+    assert!(missile.trail.len() <= MISSILE_TRAIL_MAX_POINTS);
+    assert_eq!(missile.trail[0], Vec2::ZERO);
+  }
+
+  #[test]
+  fn smart_bombs_never_climb() {
+    let starts = [Vec2::ZERO, Vec2::new(-400.0, 0.0), Vec2::new(600.0, 300.0), Vec2::new(30.0, 420.0)];
+    for pos in starts {
+      let min_descent = SMART_BOMB_MIN_DESCENT.min((TARGET - pos).normalize().y);
+      for x in -4..=4 {
+        for y in -4..=4 {
+          // Explosions all around the bomb, also several at once right below it
+          let center = pos + Vec2::new(x as f32, y as f32) * 30.0;
+          let explosions = [Explosion::new_default(center), Explosion::new_default(pos + Vec2::new(15.0, 40.0))];
+          let heading = smart_bomb_heading(pos, TARGET, &explosions);
+          assert!(heading.y >= min_descent - 1e-5, "{pos} {center} {heading}");
+          assert!((heading.length() - 1.0).abs() < 1e-5, "{heading}");
+        }
+      }
+    }
   }
 }
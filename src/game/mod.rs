@@ -1,343 +1,411 @@
-use crate::utils::mouse_pos;
-use crate::{
-  AppState,
-  utils::{
-    color_ext::ColorExt,
-    diagnostics::Diagnostics,
-    graphics::G,
-    viewport::{View, Viewport, ViewportMode},
-  },
-};
-use ::rand::Rng;
-use ::rand::rng;
 use frame::Frame;
-use macroquad::{color, prelude::*};
+use macroquad::prelude::*;
+use macroquad::color;
 use macroquad::shapes::draw_triangle;
-use rand_distr::Exp;
-use std::{process, time::Duration};
+
+use crate::audio::Audio;
+use crate::highscores::{HighScore, HighScores};
+use crate::input::{Action, Input};
+use crate::screens::game_over::GameOverScreen;
+use crate::screens::paused::PauseScreen;
+use crate::settings::{ControlScheme, Settings};
+use crate::utils::color_ext::ColorExt;
+use crate::utils::diagnostics::Diagnostics;
+use crate::utils::graphics::G;
+use crate::utils::{format_time, params};
+use crate::utils::viewport::{Viewport, ViewportMode};
+use crate::{AppState, push_state};
 
 pub mod bunker;
+pub mod camera;
+pub mod carrier;
 pub mod constants;
+pub mod difficulty;
+pub mod events;
 pub mod explosion;
 pub mod frame;
 pub mod missile;
+pub mod particles;
 pub mod player;
+pub mod replay;
+pub mod skills;
 pub mod star;
+pub mod stats;
+pub mod waves;
+pub mod world;
 
-use bunker::Bunker;
+use bunker::DamageState;
+use camera::CameraEffects;
+use carrier::CarrierKind;
 use constants::*;
-use explosion::{Explosion, ExplosionParams};
-use missile::Missile;
-use player::{Player, Skill};
-use star::Star;
+use events::GameEvent;
+use explosion::ExplosionParams;
+use missile::EnemyKind;
+use particles::Particles;
+use replay::{Replay, ReplayPlayer};
+use waves::WavePhase;
+use skills::SkillBook;
+use world::{Command, World};
+
+// Path the replay of a run is written to if no `record` parameter is given
+const DEFAULT_REPLAY_PATH: &str = "last-run.replay";
+
+// Where the commands for the world come from
+enum InputSource {
+  // The player controls the game and all commands are recorded
+  Live(Replay),
+  // The commands are read from a replay
+  Playback(ReplayPlayer),
+}
 
+/// The macroquad front-end of the game. It turns input actions into
+/// simulation commands and renders the state of the `World`.
 pub struct Game {
-  bunkers: Vec<Bunker>,
-  missiles: Vec<Missile>,
-  explosions: Vec<Explosion>,
-  stars: Vec<Star>,
-  time_until_next_missile_spawn: f32,
-  game_over: bool,
+  world: World,
+  input: InputSource,
   view_rect: Rect,
+  // Viewport of the world with the camera effects applied
   viewport: Viewport,
-  game_time: f32,
-  player: Player,
-  level_ups_left: usize,
-  // Skill selection menu state
-  skill_options: Vec<Skill>,
+  // Skill option that is selected in the skill menu
   selected_skill_index: usize,
+  // Bunker that fires on click with the keyboard control scheme
+  selected_bunker: usize,
+  // Frame time that has not been simulated yet
+  accumulator: f32,
+  // Commands waiting for the next simulation tick
+  pending_commands: Vec<Command>,
+  // Whether the game over screen was already shown for this run
+  game_over_shown: bool,
+  particles: Particles,
+  camera: CameraEffects,
 }
 
 impl Game {
-  // Calculate experience based on missile height
-  // 0 at bunker level (y=280), 100 at top of screen (y=-300)
-  fn calculate_experience_for_missile(&self, missile_pos: Vec2) -> f32 {
-    // Normalize the y position between bunker level and top of screen
-    let bunker_level = 280.0;
-    let top_level = -300.0;
-    let height_range = bunker_level - top_level;
-
-    // Calculate normalized height (0.0 at bunker level, 1.0 at top)
-    let normalized_height = (bunker_level - missile_pos.y) / height_range;
-
-    // Scale to experience (0 at bunker level, 100 at top)
-    normalized_height * 100.0
-  }
-
   pub fn new() -> Box<Game> {
-    // Create three bunkers at the bottom of the screen
-    let bunkers = vec![
-      Bunker::new(Vec2::new(-200.0, 280.0 - BUNKER_HEIGHT)),
-      Bunker::new(Vec2::new(0.0, 280.0 - BUNKER_HEIGHT)),
-      Bunker::new(Vec2::new(200.0, 280.0 - BUNKER_HEIGHT)),
-    ];
-
     let (viewport, view_rect) = Self::create_viewport();
+    let input = Self::choose_input_source();
+    let (seed, mode, difficulty) = match &input {
+      InputSource::Live(recording) => (recording.seed, recording.mode, recording.difficulty),
+      InputSource::Playback(player) => (player.seed(), player.mode(), player.difficulty()),
+    };
+    let intensity = Settings::get().effects_intensity;
 
     Box::new(Game {
-      bunkers,
-      missiles: Vec::new(),
-      explosions: Vec::new(),
-      stars: Vec::new(),
-      time_until_next_missile_spawn: INITIAL_SPAWN_TIME,
-      game_over: false,
+      world: World::new(seed, mode, difficulty),
+      input,
       view_rect,
       viewport,
-      game_time: 0.0,
-      player: Player::new(),
-      level_ups_left: 0,
-      skill_options: Vec::new(),
       selected_skill_index: 0,
+      selected_bunker: 0,
+      accumulator: 0.0,
+      pending_commands: Vec::new(),
+      game_over_shown: false,
+      particles: Particles::new(intensity),
+      camera: CameraEffects::new(intensity),
     })
   }
 
-  fn reset(&mut self) {
-    *self = *Game::new();
+  // Use the seed given as launch parameter, so every run of this session replays the same waves
+  fn choose_seed() -> u64 {
+    params::get_parsed("seed").unwrap_or_else(::rand::random)
+  }
+
+  // Play back the replay given as launch parameter, or let the player control a new run
+  fn choose_input_source() -> InputSource {
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(path) = params::get("replay") {
+      match Replay::load(&path) {
+        Ok(replay) => return InputSource::Playback(ReplayPlayer::new(replay)),
+        Err(err) => log::error!("{err}"),
+      }
+    }
+
+    let settings = Settings::get();
+    InputSource::Live(Replay::new(Self::choose_seed(), settings.game_mode, settings.difficulty))
+  }
+
+  fn is_playback(&self) -> bool {
+    matches!(self.input, InputSource::Playback(_))
   }
 
-  fn create_viewport() -> (Viewport, Rect) {
+  pub fn create_viewport() -> (Viewport, Rect) {
     let view_rect = Rect::new(-400.0, -300.0, 800., 600.);
     let screen_size = Frame::get().screen_size;
     let viewport = Viewport::fit(view_rect, screen_size, 0.0, ViewportMode::FitViewIntoScreen);
     (viewport, view_rect)
   }
 
-  fn find_closest_active_bunker(&self, target_pos: Vec2) -> Option<usize> {
-    let mut closest_idx = None;
-    let mut closest_dist = f32::MAX;
+  // The viewport of the world moved by the camera effects. The HUD is drawn with `create_viewport`.
+  fn camera_viewport(&self) -> Viewport {
+    let (_, view_rect) = Self::create_viewport();
+    let (camera_rect, rotation) = self.camera.apply(view_rect);
+    Viewport::fit(camera_rect, Frame::get().screen_size, rotation, ViewportMode::FitViewIntoScreen)
+  }
+
+  // Box of the n-th skill option in the skill selection menu
+  fn skill_option_rect(index: usize) -> Rect {
+    let x_pos = -150.0 + index as f32 * 300.0;
+    let y_pos = -50.0;
+    Rect::new(x_pos - 150.0, y_pos - 30.0, 300.0, 200.0)
+  }
+
+  // Translate the input actions into commands for the simulation
+  fn collect_commands(&mut self) -> Vec<Command> {
+    let mut commands = Vec::new();
+    let input = Input::get();
 
-    for (idx, bunker) in self.bunkers.iter().enumerate() {
-      if bunker.active && !bunker.firing {
-        let dist = bunker.pos.distance(target_pos);
-        if dist < closest_dist {
-          closest_dist = dist;
-          closest_idx = Some(idx);
+    // Get the cursor position in world coordinates
+    let world_pos = self.viewport.vec2_to_view(input.cursor());
+
+    if self.world.is_skill_selection_active() {
+      self.collect_skill_commands(&input, &mut commands);
+    } else {
+      match Settings::get().control_scheme {
+        ControlScheme::MouseNearest => {
+          if input.is_pressed(Action::Fire) {
+            // Fire missiles at the cursor
+            commands.push(Command::Fire(world_pos));
+          }
+        }
+        ControlScheme::KeyboardBunkers => self.collect_bunker_commands(&input, world_pos, &mut commands),
+      }
+
+      // Rebuild the destroyed bunker closest to the cursor
+      if input.is_pressed(Action::Rebuild) {
+        let ruin = (self.world.bunkers().iter().enumerate())
+          .filter(|(_, bunker)| !bunker.active)
+          .min_by(|(_, a), (_, b)| a.pos.distance(world_pos).total_cmp(&b.pos.distance(world_pos)));
+        if let Some((bunker_idx, _)) = ruin {
+          commands.push(Command::RebuildBunker(bunker_idx));
         }
       }
     }
 
-    closest_idx
+    commands
   }
 
-  fn spawn_enemy_missile(&mut self) {
-    let mut rng = rng();
-
-    // Random position at the top of the screen
-    let start_x = rng.random_range(-380.0..380.0);
-    let start_pos = Vec2::new(start_x, -300.0);
+  // Choose a skill by clicking it, or by moving the selection with the menu actions and confirming it
+  fn collect_skill_commands(&mut self, input: &Input, commands: &mut Vec<Command>) {
+    let option_count = self.world.skill_options().len();
+    if option_count == 0 {
+      return;
+    }
+    if input.is_pressed(Action::MenuLeft) {
+      self.selected_skill_index = (self.selected_skill_index + option_count - 1) % option_count;
+    }
+    if input.is_pressed(Action::MenuRight) {
+      self.selected_skill_index = (self.selected_skill_index + 1) % option_count;
+    }
+    self.selected_skill_index = self.selected_skill_index.min(option_count - 1);
+
+    // The skill menu is part of the HUD, which doesn't move with the camera
+    let (hud_viewport, _) = Self::create_viewport();
+    let hud_pos = hud_viewport.vec2_to_view(input.cursor());
+    let hovered = (0..option_count).find(|&i| Self::skill_option_rect(i).contains(hud_pos));
+    // Only a moving cursor selects, so it doesn't undo the selection made with the menu actions
+    if let Some(hovered) = hovered
+      && input.cursor_moved()
+    {
+      self.selected_skill_index = hovered;
+    }
 
-    // Target one of the bunkers
-    let active_bunkers: Vec<(usize, &Bunker)> = self.bunkers.iter().enumerate().filter(|(_, b)| b.active).collect();
+    if input.is_pressed(Action::Confirm) {
+      commands.push(Command::PickSkill(self.selected_skill_index));
+    } else if let Some(hovered) = hovered
+      && input.is_pressed(Action::Fire)
+    {
+      self.selected_skill_index = hovered;
+      commands.push(Command::PickSkill(hovered));
+    }
+  }
 
-    if !active_bunkers.is_empty() {
-      let random_idx = rng.random_range(0..active_bunkers.len());
-      let (target_bunker_idx, target_bunker) = active_bunkers[random_idx];
-      let target_pos = target_bunker.pos;
+  // Fire from a specific bunker with its action, or from the selected bunker with the fire action
+  fn collect_bunker_commands(&mut self, input: &Input, world_pos: Vec2, commands: &mut Vec<Command>) {
+    let bunker_count = self.world.bunkers().len();
+    for bunker_idx in 0..bunker_count {
+      if Action::fire_bunker(bunker_idx).is_some_and(|action| input.is_pressed(action)) {
+        commands.push(Command::FireFrom(bunker_idx, world_pos));
+      }
+    }
 
-      // Calculate enemy missile speed based on elapsed time
-      let speed = get_enemy_missile_speed(self.game_time);
+    // Select the next bunker that is still standing, also when the selected one was destroyed
+    if input.is_pressed(Action::NextBunker) || !self.world.bunkers()[self.selected_bunker].active {
+      self.selected_bunker = (1..=bunker_count)
+        .map(|offset| (self.selected_bunker + offset) % bunker_count)
+        .find(|&bunker_idx| self.world.bunkers()[bunker_idx].active)
+        .unwrap_or(self.selected_bunker);
+    }
 
-      self
-        .missiles
-        .push(Missile::new(start_pos, target_pos, Some(target_bunker_idx), speed));
+    if input.is_pressed(Action::Fire) {
+      commands.push(Command::FireFrom(self.selected_bunker, world_pos));
     }
   }
 
-  fn update_missiles(&mut self, dt: f32) {
-    let mut new_explosions = Vec::new();
-
-    // Update all missiles
-    for missile in &mut self.missiles {
-      if !missile.exploded {
-        missile.current_pos += missile.direction * missile.speed * dt;
-
-        match missile.target_bunker_idx {
-          // Enemy missile
-          Some(target_bunker_idx) => {
-            // Check if missile hit its target bunker
-            if target_bunker_idx < self.bunkers.len() {
-              let bunker = &mut self.bunkers[target_bunker_idx];
-
-              if missile.current_pos.distance(bunker.pos) < 2.0 {
-                missile.exploded = true;
-                bunker.active = false;
-                new_explosions.push(Explosion::new_default(bunker.pos));
-              }
-            }
-          }
-          // Player missile
-          None => {
-            // Check if missile reached target
-            if missile.current_pos.distance(missile.target_pos) < 5.0 {
-              missile.exploded = true;
-              // Use player's skills for player missiles
-              let params = ExplosionParams::from(&self.player);
-              new_explosions.push(Explosion::new(missile.current_pos, params));
-            }
+  // Advance the world in fixed ticks, so the outcome doesn't depend on the frame rate.
+  // Returns the events of all ticks.
+  fn advance_world(&mut self, frame_time: f32) -> Vec<GameEvent> {
+    self.accumulator += frame_time.min(MAX_FRAME_TIME);
+
+    let mut ticks = 0;
+    let mut events = Vec::new();
+    while self.accumulator >= SIMULATION_DT {
+      self.accumulator -= SIMULATION_DT;
+      let tick = self.world.tick();
+      let commands = match &mut self.input {
+        InputSource::Live(recording) => {
+          let commands = std::mem::take(&mut self.pending_commands);
+          for command in &commands {
+            recording.record(tick, *command);
           }
+          commands
         }
+        InputSource::Playback(player) => player.commands_for_tick(tick),
+      };
+      self.world.step(SIMULATION_DT, &commands);
+      events.extend_from_slice(self.world.events());
+      ticks += 1;
+
+      if self.world.is_game_over() {
+        break;
       }
     }
 
-    // Add all new explosions
-    self.explosions.append(&mut new_explosions);
-  }
-
-  // Spawn a star at a random position above the bunkers
-  fn spawn_star(&mut self) {
-    let mut rng = rng();
-
-    // Random position above the bunkers but below the top of the screen
-    let x = rng.random_range(-350.0..350.0);
-    let y = rng.random_range(-250.0..200.0); // Above bunkers, below top
-
-    self.stars.push(Star::new(Vec2::new(x, y)));
+    Diagnostics::get().report_number("game/ticks", ticks, "");
+    events
   }
 
-  fn update_explosions(&mut self, dt: f32) {
-    // Collect positions for new explosions
-    let mut new_explosions = Vec::new();
-    // Collect positions of destroyed enemy missiles for experience
-    let mut destroyed_enemy_missile_positions = Vec::new();
-
-    // Update existing explosions
-    for explosion in &mut self.explosions {
-      // Use the explosion's update method to handle growth mechanics
-      explosion.update(dt);
+  // Write the recorded commands of the finished run to disk.
+  // Returns a message for the player about where the replay went.
+  fn save_replay(&self) -> Option<String> {
+    let InputSource::Live(recording) = &self.input else {
+      return None;
+    };
+
+    // There is no file system in the browser, so the replay goes to the console
+    #[cfg(target_arch = "wasm32")]
+    {
+      log::info!("Replay of this run:\n{recording}");
+      None
     }
 
-    // Check for chain reactions with missiles and stars
-    for explosion in &self.explosions {
-      // Check all missiles
-      for missile in &mut self.missiles {
-        if !missile.exploded && missile.current_pos.distance(explosion.pos) <= explosion.radius {
-          missile.exploded = true;
-          // Check if it's a player missile or an enemy missile
-          if missile.is_player_missile() {
-            // Player missile - use player's skills
-            let params = ExplosionParams::from(&self.player);
-            new_explosions.push(Explosion::new(missile.current_pos, params));
-          } else {
-            // Enemy missile - use default parameters
-            new_explosions.push(Explosion::new_default(missile.current_pos));
-            // Store position for experience calculation
-            destroyed_enemy_missile_positions.push(missile.current_pos);
-          }
-        }
-      }
-
-      // Check if any stars are hit by this explosion
-      self.stars.retain(|star| {
-        if star.is_hit_by_explosion(explosion.pos, explosion.radius) {
-          self.level_ups_left += 1;
-          false
-        } else {
-          true
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+      let path = params::get("record").unwrap_or_else(|| DEFAULT_REPLAY_PATH.to_string());
+      Some(match recording.save(&path) {
+        Ok(()) => format!("Replay saved to {path}"),
+        Err(err) => {
+          log::error!("{err}");
+          "Replay could not be saved".to_string()
         }
       })
     }
+  }
 
-    // Award experience for destroyed enemy missiles
-    for pos in destroyed_enemy_missile_positions {
-      let exp = self.calculate_experience_for_missile(pos);
-      let new_stars = self.player.add_experience(exp);
-
-      for _ in 0..new_stars {
-        self.spawn_star()
-      }
-    }
-
-    // Add all new explosions
-    self.explosions.append(&mut new_explosions);
+  fn show_game_over_screen(&mut self) {
+    self.game_over_shown = true;
+    Audio::get().stop_music();
+    let replay_message = self.save_replay();
 
-    // Remove finished explosions
-    self.explosions.retain(|e| !e.has_ended());
+    // Watching a replay is not a new run, so only live games make it into the high scores
+    let mut high_scores = HighScores::load();
+    let rank = if self.is_playback() {
+      None
+    } else {
+      let rank = high_scores.insert(HighScore::from_world(&self.world));
+      high_scores.save();
+      rank
+    };
 
-    // Remove exploded missiles
-    self.missiles.retain(|m| !m.exploded);
+    push_state(GameOverScreen::new(&self.world, replay_message, high_scores, rank));
+  }
 
-    // Update bunker firing status
-    for bunker in &mut self.bunkers {
-      if bunker.firing {
-        let still_firing = self
-          .missiles
-          .iter()
-          .any(|m| m.target_bunker_idx.is_none() && !m.exploded && m.start_pos.distance(bunker.pos) < 5.0);
-        bunker.firing = still_firing;
-      }
-    }
+  // How far we are between the last and the next simulation tick
+  fn interpolation_alpha(&self) -> f32 {
+    self.accumulator / SIMULATION_DT
   }
 
-  fn check_game_over(&mut self) {
-    // Game is over if all bunkers are destroyed
-    if self.bunkers.iter().all(|b| !b.active) {
-      self.game_over = true;
-    }
+  // The reticle replaces the mouse cursor, except while choosing a skill or watching a replay
+  fn is_aiming(&self) -> bool {
+    !self.world.is_skill_selection_active() && !self.world.is_game_over() && !self.is_playback()
   }
 
-  // Show the skill selection menu with two random skills
-  fn show_skill_selection_menu(&mut self) {
-    if self.skill_options.is_empty() && self.level_ups_left > 0 {
-      // Get two random skills
-      self.skill_options = Skill::random_subset(2);
-      self.selected_skill_index = 0;
+  // Bunker that would fire at `target` with the current control scheme, if any can fire
+  fn firing_bunker(&self, target: Vec2) -> Option<usize> {
+    match Settings::get().control_scheme {
+      ControlScheme::MouseNearest => self.world.find_closest_active_bunker(target),
+      ControlScheme::KeyboardBunkers => {
+        Some(self.selected_bunker).filter(|&bunker_idx| self.world.bunkers()[bunker_idx].can_fire())
+      }
     }
   }
 
-  // Handle player input for skill selection
-  fn handle_skill_selection_input(&mut self) {
-    if !self.is_skill_selection_active() {
+  // Reticle at the cursor with the bunker that would fire, the flight time of the shot and the size of its explosion.
+  // With aim assist the enemy missiles are marked where they will be when the shot arrives.
+  fn draw_aim_preview(&self, cursor: Vec2) {
+    let Some(bunker_idx) = self.firing_bunker(cursor) else {
+      // No bunker can fire right now
+      Self::draw_reticle(cursor, color::RED);
       return;
+    };
+
+    let player = self.world.player();
+    let bunker_pos = self.world.bunkers()[bunker_idx].pos;
+    let flight_time = bunker_pos.distance(cursor) / player.get_missile_speed();
+    let radius = ExplosionParams::from(player).max_radius;
+
+    G::line(bunker_pos, cursor, 1.0, color::WHITE.with_alpha(0.15));
+    G::circle(bunker_pos, 14.0, 1.0, color::WHITE.with_alpha(0.5));
+
+    // Split missiles explode in smaller warheads around the target
+    let warheads = player.get_warheads();
+    if warheads > 1 {
+      for i in 0..warheads {
+        let angle = std::f32::consts::TAU * i as f32 / warheads as f32;
+        let warhead_pos = cursor + Vec2::from_angle(angle) * WARHEAD_SPREAD;
+        G::circle(warhead_pos, radius * WARHEAD_EXPLOSION_SCALE, 1.0, color::WHITE.with_alpha(0.2));
+      }
+    } else {
+      G::circle(cursor, radius, 1.0, color::WHITE.with_alpha(0.3));
     }
 
-    // Get mouse position in world coordinates
-    let mouse_pos = mouse_pos();
-    let world_pos = self.viewport.vec2_to_view(mouse_pos);
-
-    // Check if mouse is hovering over any skill option
-    for (i, _) in self.skill_options.iter().enumerate() {
-      let x_pos = -150.0 + i as f32 * 300.0;
-      let y_pos = -50.0;
-      let box_x = x_pos - 150.0;
-      let box_y = y_pos - 30.0;
-      let box_width = 300.0;
-      let box_height = 200.0;
-
-      // Check if mouse is inside this skill's box
-      if world_pos.x >= box_x && world_pos.x <= box_x + box_width &&
-         world_pos.y >= box_y && world_pos.y <= box_y + box_height {
-        // Update selected skill index
-        self.selected_skill_index = i;
-
-        // Select skill with mouse click
-        if is_mouse_button_pressed(MouseButton::Left) {
-          if let Some(selected_skill) = self.skill_options.get(self.selected_skill_index) {
-            // Level up the selected skill
-            self.player.level_up_skill(*selected_skill);
-            self.level_ups_left -= 1;
-            self.skill_options.clear();
-
-            // If there are more level ups left, show the menu again
-            if self.level_ups_left > 0 {
-              self.show_skill_selection_menu();
-            }
-          }
-        }
+    Self::draw_reticle(cursor, color::WHITE);
+    G::centered_text(&format!("{flight_time:.1}s"), cursor.x + 24.0, cursor.y - 14.0, 14.0, color::WHITE);
 
-        break;
+    if Settings::get().aim_assist {
+      // The whole area the warheads cover counts as in reach
+      let reach = if warheads > 1 { WARHEAD_SPREAD + radius * WARHEAD_EXPLOSION_SCALE } else { radius };
+      for missile in self.world.missiles() {
+        if missile.exploded || missile.is_player_missile() {
+          continue;
+        }
+        let predicted_pos = missile.predicted_pos(flight_time);
+        let color = if predicted_pos.distance(cursor) <= reach { color::GREEN } else { color::GRAY };
+        G::line(missile.current_pos, predicted_pos, 1.0, color.with_alpha(0.3));
+        G::circle(predicted_pos, 4.0, 1.0, color);
       }
     }
   }
 
-  fn render(&self) {
+  fn draw_reticle(pos: Vec2, color: Color) {
+    G::circle(pos, 8.0, 1.0, color);
+    for direction in [Vec2::X, Vec2::Y, -Vec2::X, -Vec2::Y] {
+      G::line(pos + direction * 4.0, pos + direction * 12.0, 1.0, color);
+    }
+  }
+
+  fn draw(&self) {
+    let alpha = self.interpolation_alpha();
+
     clear_background(BLACK);
 
-    // Use the stored viewport
-    self.viewport.set_as_camera();
-    G::filled_rect(self.view_rect, color::DARKGRAY.mul(0.5));
+    // The viewport is created anew, because rendering continues while other states are updated
+    let (hud_viewport, view_rect) = Self::create_viewport();
+    let camera_viewport = self.camera_viewport();
+    camera_viewport.set_as_camera();
+    G::filled_rect(view_rect, color::DARKGRAY.mul(0.5));
 
     // Draw experience bar at the top of the screen
-    let exp_progress = self.player.experience_progress();
+    let exp_progress = self.world.player().experience_progress();
     let exp_bar_width = 800.0 * exp_progress;
     let exp_bar_rect = Rect::new(-400.0, -300.0, exp_bar_width, 3.0);
     G::filled_rect(exp_bar_rect, color::BLUE);
@@ -347,59 +415,201 @@ impl Game {
     G::filled_rect(ground_rect, color::YELLOW);
 
     // Draw bunkers
-    for bunker in &self.bunkers {
-      if bunker.active {
-        // Calculate the slope amount (how much the sides are angled)
-        let slope_amount = BUNKER_HEIGHT / 2.0;
-
-        // Calculate the points for the trapezoid
-        let bottom_left = Vec2::new(bunker.pos.x - BUNKER_WIDTH / 2.0, bunker.pos.y + BUNKER_HEIGHT);
-        let bottom_right = Vec2::new(bunker.pos.x + BUNKER_WIDTH / 2.0, bunker.pos.y + BUNKER_HEIGHT);
-        let top_right = Vec2::new(bunker.pos.x + BUNKER_WIDTH / 2.0 - slope_amount, bunker.pos.y);
-        let top_left = Vec2::new(bunker.pos.x - BUNKER_WIDTH / 2.0 + slope_amount, bunker.pos.y);
-
-        // Draw the trapezoid using triangles
-        draw_triangle(bottom_left, bottom_right, top_right, color::YELLOW);
-        draw_triangle(bottom_left, top_left, top_right, color::YELLOW);
+    for (bunker_idx, bunker) in self.world.bunkers().iter().enumerate() {
+      let state = bunker.damage_state();
+      if state == DamageState::Destroyed {
+        // Show the rebuild progress over the ruin
+        let bar_rect = Rect::new(bunker.pos.x - BUNKER_WIDTH / 2.0, bunker.pos.y - 10.0, BUNKER_WIDTH, 5.0);
+        G::filled_rect(bar_rect, color::DARKGRAY);
+        G::filled_rect(
+          Rect::new(bar_rect.x, bar_rect.y, bar_rect.w * bunker.rebuild_progress.min(1.0), bar_rect.h),
+          color::ORANGE,
+        );
+        if self.world.can_afford_rebuild() && !self.is_playback() {
+          G::centered_text("R: Rebuild", bunker.pos.x, bunker.pos.y - 22.0, 12.0, color::WHITE);
+        }
+        continue;
+      }
+
+      // Calculate the slope amount (how much the sides are angled)
+      let slope_amount = BUNKER_HEIGHT / 2.0;
+
+      // Calculate the points for the trapezoid
+      let bottom_left = Vec2::new(bunker.pos.x - BUNKER_WIDTH / 2.0, bunker.pos.y + BUNKER_HEIGHT);
+      let bottom_right = Vec2::new(bunker.pos.x + BUNKER_WIDTH / 2.0, bunker.pos.y + BUNKER_HEIGHT);
+      let top_right = Vec2::new(bunker.pos.x + BUNKER_WIDTH / 2.0 - slope_amount, bunker.pos.y);
+      let top_left = Vec2::new(bunker.pos.x - BUNKER_WIDTH / 2.0 + slope_amount, bunker.pos.y);
+
+      // Draw the trapezoid using triangles, darker the more damaged the bunker is
+      let bunker_color = match state {
+        DamageState::Intact => color::YELLOW,
+        DamageState::Cracked => color::GOLD,
+        _ => color::ORANGE.mul(0.8),
+      };
+      draw_triangle(bottom_left, bottom_right, top_right, bunker_color);
+      draw_triangle(bottom_left, top_left, top_right, bunker_color);
+
+      // Cracks through the walls
+      if state != DamageState::Intact {
+        let crack_color = color::BROWN;
+        let x = bunker.pos.x;
+        let y = bunker.pos.y;
+        G::polyline(&[Vec2::new(x - 8.0, y), Vec2::new(x - 3.0, y + 8.0), Vec2::new(x - 9.0, y + 14.0)], 1.5, crack_color);
+        G::line(Vec2::new(x + 10.0, y + BUNKER_HEIGHT), Vec2::new(x + 6.0, y + 11.0), 1.5, crack_color);
+      }
+
+      // Mark the bunker that fires on click
+      let keyboard_controls = Settings::get().control_scheme == ControlScheme::KeyboardBunkers;
+      if keyboard_controls && bunker_idx == self.selected_bunker && !self.is_playback() {
+        let tip = Vec2::new(bunker.pos.x, bunker.pos.y - 6.0);
+        draw_triangle(tip, tip + Vec2::new(-6.0, -8.0), tip + Vec2::new(6.0, -8.0), color::WHITE);
+      }
+
+      // Ammo pips on the ground beneath the bunker, the next round fills up while it is loading
+      let magazine_size = self.world.player().get_magazine_size();
+      let reload_progress = bunker.reload_timer / self.world.player().get_reload_time();
+      let pip_size = 5.0;
+      let pip_spacing = 8.0;
+      let first_x = bunker.pos.x - (magazine_size - 1) as f32 * pip_spacing / 2.0;
+      for i in 0..magazine_size {
+        let pip_rect = Rect::new(first_x + i as f32 * pip_spacing - pip_size / 2.0, GROUND_LEVEL + 7.0, pip_size, pip_size);
+        if i < bunker.ammo {
+          G::filled_rect(pip_rect, color::DARKBLUE);
+        } else {
+          G::rect_outline(pip_rect, 1.0, color::DARKBLUE);
+          if i == bunker.ammo {
+            let fill_height = pip_rect.h * reload_progress.min(1.0);
+            let fill_rect = Rect::new(pip_rect.x, pip_rect.bottom() - fill_height, pip_rect.w, fill_height);
+            G::filled_rect(fill_rect, color::DARKBLUE.with_alpha(0.5));
+          }
+        }
+      }
+
+      // Flickering flames on top
+      if state == DamageState::Burning {
+        let time = self.world.game_time();
+        for i in 0..3 {
+          let x = bunker.pos.x + (i as f32 - 1.0) * 8.0;
+          let height = 8.0 + 4.0 * (time * 15.0 + i as f32 * 2.0).sin();
+          let base = bunker.pos.y + 1.0;
+          draw_triangle(
+            Vec2::new(x - 4.0, base),
+            Vec2::new(x + 4.0, base),
+            Vec2::new(x, base - height),
+            if i == 1 { color::RED } else { color::ORANGE },
+          );
+        }
       }
     }
 
+    self.particles.draw();
+
     // Draw all missiles
-    for missile in &self.missiles {
+    for missile in self.world.missiles() {
       if !missile.exploded {
-        G::line(missile.start_pos, missile.current_pos, 1.0, color::WHITE);
+        let trail_color = match missile.enemy_kind() {
+          None | Some(EnemyKind::Standard) => color::WHITE,
+          Some(EnemyKind::Mirv) => color::MAGENTA,
+          Some(EnemyKind::Evasive) => color::LIME,
+          Some(EnemyKind::Dart) => color::SKYBLUE,
+          Some(EnemyKind::SmartBomb) => color::ORANGE,
+        };
+        // The stored trail ends where the missile last changed its heading, from there it leads to the missile
+        G::polyline(&missile.trail, 1.0, trail_color);
+        if let Some(&corner) = missile.trail.last() {
+          G::line(corner, missile.interpolated_pos(alpha), 1.0, trail_color);
+        }
       }
     }
 
-    // Draw explosions
-    for explosion in &self.explosions {
-      G::circle(explosion.pos, explosion.radius, 1.0, color::WHITE);
+    // Draw explosions as a glowing ball with a bright rim
+    for explosion in self.world.explosions() {
+      let radius = explosion.interpolated_radius(alpha);
+      let glow_color = if explosion.hostile { color::ORANGE } else { color::YELLOW };
+      G::filled_circle(explosion.pos, radius, glow_color.with_alpha(0.25));
+      G::circle(explosion.pos, radius, 2.0, color::WHITE);
+    }
+
+    // Draw carriers
+    for carrier in self.world.carriers() {
+      let pos = carrier.interpolated_pos(alpha);
+      let facing = carrier.velocity.x.signum();
+      match carrier.kind {
+        CarrierKind::Bomber => {
+          // Fuselage pointing in the flight direction with swept back wings
+          let nose = pos + Vec2::new(facing * 14.0, 0.0);
+          let tail = pos - Vec2::new(facing * 14.0, 0.0);
+          G::line(tail, nose, 4.0, color::RED);
+          draw_triangle(
+            pos + Vec2::new(facing * 4.0, 0.0),
+            pos + Vec2::new(-facing * 6.0, -10.0),
+            pos + Vec2::new(-facing * 6.0, 10.0),
+            color::RED,
+          );
+        }
+        CarrierKind::Satellite => {
+          // Body with two solar panels
+          G::filled_rect(Rect::new(pos.x - 4.0, pos.y - 4.0, 8.0, 8.0), color::LIGHTGRAY);
+          G::rect_outline(Rect::new(pos.x - 16.0, pos.y - 3.0, 9.0, 6.0), 1.0, color::SKYBLUE);
+          G::rect_outline(Rect::new(pos.x + 7.0, pos.y - 3.0, 9.0, 6.0), 1.0, color::SKYBLUE);
+        }
+      }
     }
 
     // Draw stars
-    for star in &self.stars {
+    for star in self.world.stars() {
       if star.active {
         // Draw a blue star (a circle for now)
         G::circle(star.pos, star.radius, 1.0, color::BLUE);
       }
     }
 
+    if self.is_aiming() {
+      let cursor = Input::get().cursor();
+      self.draw_aim_preview(camera_viewport.vec2_to_view(cursor));
+    }
+
+    // The flash and the HUD stay in place while the camera moves
+    hud_viewport.set_as_camera();
+    self.particles.draw_flash(view_rect);
+
     // Draw timer in top right corner
-    let timer_text = Game::format_time(self.game_time);
+    let timer_text = format_time(self.world.game_time());
     G::centered_text(&timer_text, 350.0, -280.0, 20.0, color::WHITE);
 
+    // Mark replays, so they are not confused with a live game
+    if self.is_playback() {
+      G::centered_text("REPLAY", -350.0, -280.0, 20.0, color::RED);
+    }
+
     // Draw player level under the timer
-    let level_text = format!("Level: {}", self.player.player_level());
+    let level_text = format!("Level: {}", self.world.player().player_level());
     G::centered_text(&level_text, 350.0, -255.0, 16.0, color::WHITE);
 
-    // Draw game over text
-    if self.game_over {
-      G::centered_text("GAME OVER", 0.0, -20.0, 40.0, color::WHITE);
-      G::centered_text("Press any key to restart", 0.0, 20.0, 20.0, color::WHITE);
+    // Draw the wave and the bonus tally between waves
+    if let Some(wave) = self.world.wave() {
+      let wave_text = format!("Wave: {}", wave.number);
+      G::centered_text(&wave_text, 350.0, -235.0, 16.0, color::WHITE);
+
+      if let WavePhase::Break { time_left, bonus } = wave.phase {
+        G::centered_text(&format!("WAVE {} CLEARED", wave.number), 0.0, -120.0, 30.0, color::YELLOW);
+        let mut lines = vec![
+          format!("Bunkers standing: {} x {:.0} XP", bonus.surviving_bunkers, WAVE_BUNKER_BONUS),
+          format!("Unused missiles: {} x {:.0} XP", bonus.unused_ammo, WAVE_AMMO_BONUS),
+          format!("Bonus: {:.0} XP", bonus.experience),
+        ];
+        if bonus.rebuilt_bunker {
+          lines.push("A destroyed bunker was rebuilt".to_string());
+        }
+        lines.push(format!("Next wave in {:.0}", time_left.ceil()));
+        for (i, line) in lines.iter().enumerate() {
+          G::centered_text(line, 0.0, -85.0 + i as f32 * 22.0, 18.0, color::WHITE);
+        }
+      }
     }
 
     // Draw skill selection menu if active
-    if self.is_skill_selection_active() {
+    if self.world.is_skill_selection_active() {
       // Draw semi-transparent background
       let menu_bg = Rect::new(-300.0, -200.0, 600.0, 400.0);
       G::filled_rect(menu_bg, color::BLACK.with_alpha(0.8));
@@ -409,130 +619,79 @@ impl Game {
       G::centered_text("Choose a skill to improve:", 0.0, -110.0, 20.0, color::WHITE);
 
       // Draw skill options
-      for (i, skill) in self.skill_options.iter().enumerate() {
+      for (i, skill_id) in self.world.skill_options().iter().enumerate() {
+        let Some(skill) = SkillBook::get().skill(skill_id) else {
+          continue;
+        };
         let x_pos = -150.0 + i as f32 * 300.0;
         let y_pos = -50.0;
         let is_selected = i == self.selected_skill_index;
 
         // Draw selection box
         if is_selected {
-          let box_x = x_pos - 150.0;
-          let box_y = y_pos - 30.0;
-          let box_width = 300.0;
-          let box_height = 200.0;
-
-          // Draw the four lines of the rectangle
-          G::line(Vec2::new(box_x, box_y), Vec2::new(box_x + box_width, box_y), 2.0, color::YELLOW); // Top
-          G::line(Vec2::new(box_x, box_y), Vec2::new(box_x, box_y + box_height), 2.0, color::YELLOW); // Left
-          G::line(Vec2::new(box_x + box_width, box_y), Vec2::new(box_x + box_width, box_y + box_height), 2.0, color::YELLOW); // Right
-          G::line(Vec2::new(box_x, box_y + box_height), Vec2::new(box_x + box_width, box_y + box_height), 2.0, color::YELLOW); // Bottom
+          G::rect_outline(Self::skill_option_rect(i), 2.0, color::YELLOW);
         }
 
         // Draw skill name
         let color = if is_selected { color::YELLOW } else { color::WHITE };
-        G::centered_text(skill.name(), x_pos, y_pos, 25.0, color);
+        G::centered_text(&skill.name, x_pos, y_pos, 25.0, color);
 
         // Draw current level
-        let level = self.player.get_skill_level(*skill);
-        let level_text = format!("Current Level: {}", level);
+        let level = self.world.player().get_skill_level(skill_id);
+        let level_text = format!("Current Level: {} / {}", level, skill.max_level);
         G::centered_text(&level_text, x_pos, y_pos + 40.0, 20.0, color);
 
         // Draw description
-        G::centered_text(skill.description(), x_pos, y_pos + 80.0, 15.0, color);
+        G::centered_text(&skill.description, x_pos, y_pos + 80.0, 15.0, color);
       }
 
       // Draw instructions
-      G::centered_text("Hover over a skill and click to select", 0.0, 150.0, 15.0, color::WHITE);
+      G::centered_text("Click a skill, or choose with left / right and confirm", 0.0, 150.0, 15.0, color::WHITE);
     }
   }
-
-  fn is_skill_selection_active(&self) -> bool {
-    self.level_ups_left > 0
-  }
-
-  // Format elapsed time as mm:ss
-  fn format_time(seconds: f32) -> String {
-    let total_seconds = seconds as u32;
-    let minutes = total_seconds / 60;
-    let seconds = total_seconds % 60;
-    format!("{:02}:{:02}", minutes, seconds)
-  }
 }
 
 impl AppState for Game {
   fn update(&mut self) {
-    #[cfg(not(any(target_arch = "wasm32", target_os = "unknown")))]
-    if is_key_down(KeyCode::Escape) {
-      process::exit(0);
+    // Pause on request, or if the frame took so long that the game stalled or its browser tab was hidden
+    if Input::get().is_pressed(Action::Pause) || Frame::get().t > PAUSE_FRAME_TIME {
+      Audio::get().stop_music();
+      push_state(PauseScreen::new());
+      return;
     }
 
-    Diagnostics::update();
-    Frame::update();
-
     // Update viewport with current screen size
-    let (viewport, view_rect) = Self::create_viewport();
-    self.viewport = viewport;
+    let (_, view_rect) = Self::create_viewport();
+    self.viewport = self.camera_viewport();
     self.view_rect = view_rect;
 
-    let dt = Frame::get().t;
-
-    if self.game_over {
-      if !get_keys_down().is_empty() || is_mouse_button_released(MouseButton::Left) {
-        self.reset();
+    if !self.is_playback() {
+      let mut commands = self.collect_commands();
+      self.pending_commands.append(&mut commands);
+    }
+    let frame_time = Frame::get().t;
+    let events = self.advance_world(frame_time);
+    Audio::get().play_events(&events);
+
+    self.particles.set_intensity(Settings::get().effects_intensity);
+    self.particles.handle_events(&events);
+    self.particles.update(frame_time, self.world.missiles());
+    self.camera.set_intensity(Settings::get().effects_intensity);
+    self.camera.handle_events(&events);
+    self.camera.update(frame_time);
+    Diagnostics::get().report_number("game/particles", self.particles.count() as f64, "");
+    show_mouse(!self.is_aiming());
+
+    if self.world.is_game_over() {
+      if !self.game_over_shown {
+        self.show_game_over_screen();
       }
     } else {
-      // Show skill selection menu if there are level ups available
-      self.show_skill_selection_menu();
-
-      // Handle skill selection input
-      if self.is_skill_selection_active() {
-        self.handle_skill_selection_input();
-      } else {
-        // Update game time
-        if !self.game_over {
-          self.game_time += dt;
-        }
-
-        // Handle mouse click for firing missiles
-        if is_mouse_button_pressed(MouseButton::Left) {
-          let mouse_pos = mouse_pos();
-          let world_pos = self.viewport.vec2_to_view(mouse_pos);
-
-          if let Some(bunker_idx) = self.find_closest_active_bunker(world_pos) {
-            let bunker = &mut self.bunkers[bunker_idx];
-            bunker.firing = true;
-
-            // Use player's missile speed skill
-            let missile_speed = self.player.get_missile_speed();
-            self.missiles.push(Missile::new(bunker.pos, world_pos, None, missile_speed));
-          }
-        }
-
-        // Spawn enemy missiles
-        self.time_until_next_missile_spawn -= dt;
-
-        if self.time_until_next_missile_spawn <= 0.0 {
-          self.spawn_enemy_missile();
-
-          // Get base spawn interval
-          let spawn_interval = get_enemy_missile_spawn_interval(self.game_time);
-
-          // Randomize by ±50%
-          let mut rng = rng();
-          let random_factor = rng.random_range(0.5..1.5);
-          self.time_until_next_missile_spawn = spawn_interval * random_factor;
-        }
-
-        // Update game state
-        self.update_missiles(dt);
-        self.update_explosions(dt);
-        self.check_game_over();
-      }
+      Audio::get().update_music(self.world.game_time());
     }
+  }
 
-    self.render();
-
-    set_default_camera();
-    Diagnostics::render();
+  fn render(&self) {
+    self.draw();
   }
 }
@@ -0,0 +1,267 @@
+use macroquad::color::{self, Color};
+use macroquad::prelude::{Rect, Vec2};
+use macroquad::rand::gen_range;
+
+use crate::game::constants::{EXPLOSION_MAX_RADIUS, GROUND_LEVEL};
+use crate::game::events::GameEvent;
+use crate::game::missile::Missile;
+use crate::settings::EffectsIntensity;
+use crate::utils::color_ext::ColorExt;
+use crate::utils::graphics::G;
+
+// Seconds between two trail particles of a missile
+const TRAIL_INTERVAL: f32 = 0.02;
+// How fast the screen flash fades, in intensity per second
+const FLASH_FADE: f32 = 3.0;
+const GRAVITY: f32 = 300.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ParticleKind {
+  /// Bright streak that flies away from an explosion
+  Spark,
+  /// Slowly rising puff that grows while it fades
+  Smoke,
+  /// Chunk that falls down and comes to rest on the ground
+  Debris,
+  /// Dot left behind by a missile
+  Trail,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Particle {
+  kind: ParticleKind,
+  pos: Vec2,
+  velocity: Vec2,
+  age: f32,
+  lifetime: f32,
+  size: f32,
+  color: Color,
+}
+
+impl Particle {
+  fn new(kind: ParticleKind, pos: Vec2, lifetime: f32, size: f32) -> Self {
+    Self {
+      kind,
+      pos,
+      velocity: Vec2::ZERO,
+      age: 0.0,
+      lifetime,
+      size,
+      color: color::WHITE,
+    }
+  }
+
+  // From 1.0 when the particle is created to 0.0 when it dies
+  fn life_left(&self) -> f32 {
+    1.0 - self.age / self.lifetime
+  }
+
+  fn update(&mut self, dt: f32) {
+    self.age += dt;
+    match self.kind {
+      ParticleKind::Spark => self.velocity *= 1.0 - 3.0 * dt,
+      ParticleKind::Smoke => {
+        self.velocity *= 1.0 - 1.5 * dt;
+        self.velocity.y -= 15.0 * dt;
+      }
+      ParticleKind::Debris => self.velocity.y += GRAVITY * dt,
+      ParticleKind::Trail => {}
+    }
+    self.pos += self.velocity * dt;
+
+    // Debris stops on the ground
+    if self.kind == ParticleKind::Debris && self.pos.y >= GROUND_LEVEL {
+      self.pos.y = GROUND_LEVEL;
+      self.velocity = Vec2::ZERO;
+    }
+  }
+
+  fn draw(&self) {
+    let life = self.life_left();
+    match self.kind {
+      ParticleKind::Spark => {
+        let tail = self.pos - self.velocity * 0.04;
+        G::line(tail, self.pos, self.size, self.color.with_alpha(life));
+      }
+      ParticleKind::Smoke => {
+        let radius = self.size * (2.0 - life);
+        G::filled_circle(self.pos, radius, self.color.with_alpha(0.3 * life));
+      }
+      ParticleKind::Debris => {
+        let rect = Rect::new(self.pos.x - self.size / 2.0, self.pos.y - self.size, self.size, self.size);
+        G::filled_rect(rect, self.color.with_alpha(life.min(0.3) / 0.3));
+      }
+      ParticleKind::Trail => G::filled_circle(self.pos, self.size * life, self.color.with_alpha(0.6 * life)),
+    }
+  }
+}
+
+/// Sparks, smoke, debris and missile trails, plus a flash of the whole screen.
+///
+/// All particles live in a pool that is allocated once, new particles are dropped while it is full.
+/// The effects are created from the events of the world and use their own randomness,
+/// so they never influence the simulation. With the intensity `Off` nothing is stored at all.
+pub struct Particles {
+  pool: Vec<Particle>,
+  capacity: usize,
+  // Scales the number of particles per effect
+  amount: f32,
+  flash: f32,
+  flash_enabled: bool,
+  time_until_next_trail: f32,
+}
+
+impl Particles {
+  pub fn new(intensity: EffectsIntensity) -> Self {
+    let mut particles = Self {
+      pool: Vec::new(),
+      capacity: 0,
+      amount: 0.0,
+      flash: 0.0,
+      flash_enabled: false,
+      time_until_next_trail: 0.0,
+    };
+    particles.set_intensity(intensity);
+    particles
+  }
+
+  /// Adjust the amount of particles, dropping the ones that no longer fit into the pool
+  pub fn set_intensity(&mut self, intensity: EffectsIntensity) {
+    let (capacity, amount) = match intensity {
+      EffectsIntensity::Off => (0, 0.0),
+      EffectsIntensity::Low => (500, 0.4),
+      EffectsIntensity::Normal => (2000, 1.0),
+      EffectsIntensity::High => (5000, 2.0),
+    };
+    if capacity == self.capacity {
+      return;
+    }
+    self.capacity = capacity;
+    self.amount = amount;
+    self.flash_enabled = intensity != EffectsIntensity::Off;
+    self.pool.truncate(capacity);
+    self.pool.reserve_exact(capacity - self.pool.len());
+  }
+
+  pub fn count(&self) -> usize {
+    self.pool.len()
+  }
+
+  /// Brightness of the screen flash from 0.0 to 1.0
+  pub fn flash(&self) -> f32 {
+    self.flash
+  }
+
+  fn spawn(&mut self, particle: Particle) {
+    if self.pool.len() < self.capacity {
+      self.pool.push(particle);
+    }
+  }
+
+  // Number of particles for an effect that has `base` particles on normal intensity
+  fn amount_of(&self, base: f32) -> usize {
+    (base * self.amount).round() as usize
+  }
+
+  fn add_flash(&mut self, intensity: f32) {
+    if self.flash_enabled {
+      self.flash = self.flash.max(intensity);
+    }
+  }
+
+  // Spawn `count` copies of `particle` that fly in random directions with up to `speed`
+  fn burst(&mut self, count: usize, speed: f32, particle: Particle, colors: &[Color]) {
+    for _ in 0..count {
+      let direction = Vec2::from_angle(gen_range(0.0, std::f32::consts::TAU));
+      self.spawn(Particle {
+        velocity: direction * speed * gen_range(0.3, 1.0),
+        lifetime: particle.lifetime * gen_range(0.6, 1.0),
+        color: colors[gen_range(0, colors.len())],
+        ..particle
+      });
+    }
+  }
+
+  /// Create the effects for the events of the last simulation steps
+  pub fn handle_events(&mut self, events: &[GameEvent]) {
+    for event in events {
+      match *event {
+        GameEvent::MissileLaunched { pos } => {
+          let smoke = Particle::new(ParticleKind::Smoke, pos, 0.6, 3.0);
+          self.burst(self.amount_of(4.0), 20.0, smoke, &[color::GRAY]);
+        }
+        GameEvent::Explosion {
+          pos,
+          max_radius,
+          hostile,
+        } => {
+          let size = max_radius / EXPLOSION_MAX_RADIUS;
+          let colors: &[Color] = if hostile {
+            &[color::ORANGE, color::RED, color::YELLOW]
+          } else {
+            &[color::WHITE, color::YELLOW, color::ORANGE]
+          };
+          let spark = Particle::new(ParticleKind::Spark, pos, 0.5, 1.5);
+          self.burst(self.amount_of(12.0 * size), max_radius * 4.0, spark, colors);
+          let smoke = Particle::new(ParticleKind::Smoke, pos, 1.5, max_radius * 0.25);
+          self.burst(self.amount_of(5.0 * size), max_radius * 0.8, smoke, &[color::DARKGRAY]);
+          self.add_flash(0.08 * size);
+        }
+        GameEvent::BunkerDestroyed { pos, .. } => {
+          let debris = self.amount_of(25.0);
+          // Debris is thrown upwards and rains back down
+          for _ in 0..debris {
+            self.spawn(Particle {
+              velocity: Vec2::new(gen_range(-120.0, 120.0), gen_range(-220.0, -60.0)),
+              color: if gen_range(0, 2) == 0 { color::YELLOW } else { color::BROWN },
+              ..Particle::new(ParticleKind::Debris, pos, gen_range(2.0, 4.0), gen_range(2.0, 4.0))
+            });
+          }
+          let smoke = Particle::new(ParticleKind::Smoke, pos, 2.5, 8.0);
+          self.burst(self.amount_of(10.0), 30.0, smoke, &[color::DARKGRAY, color::GRAY]);
+          self.add_flash(0.4);
+        }
+        GameEvent::StarCollected { pos } => {
+          let spark = Particle::new(ParticleKind::Spark, pos, 0.6, 1.5);
+          self.burst(self.amount_of(20.0), 150.0, spark, &[color::BLUE, color::SKYBLUE]);
+        }
+        GameEvent::MissileIntercepted { .. } | GameEvent::LevelUp => {}
+        GameEvent::GameOver => self.add_flash(0.8),
+      }
+    }
+  }
+
+  /// Age all particles, leave trails behind the missiles and fade the flash
+  pub fn update(&mut self, dt: f32, missiles: &[Missile]) {
+    for particle in &mut self.pool {
+      particle.update(dt);
+    }
+    self.pool.retain(|particle| particle.age < particle.lifetime);
+
+    self.time_until_next_trail -= dt;
+    if self.time_until_next_trail <= 0.0 {
+      self.time_until_next_trail = TRAIL_INTERVAL;
+      for missile in missiles.iter().filter(|missile| !missile.exploded) {
+        self.spawn(Particle {
+          color: color::LIGHTGRAY,
+          ..Particle::new(ParticleKind::Trail, missile.current_pos, 0.4, 1.5)
+        });
+      }
+    }
+
+    self.flash = (self.flash - FLASH_FADE * dt).max(0.0);
+  }
+
+  pub fn draw(&self) {
+    for particle in &self.pool {
+      particle.draw();
+    }
+  }
+
+  /// Draw the screen flash over `view_rect`
+  pub fn draw_flash(&self, view_rect: Rect) {
+    if self.flash > 0.0 {
+      G::filled_rect(view_rect, color::WHITE.with_alpha(self.flash));
+    }
+  }
+}
@@ -1,65 +1,17 @@
-use crate::game::constants::{EXPLOSION_AFTER_GLOW, EXPLOSION_GROWTH_RATE, EXPLOSION_MAX_RADIUS, MISSILE_SPEED};
-use rand::seq::SliceRandom;
-
-/// Enum representing the different skills a player can level up
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum Skill {
-    ExplosionSpeed,
-    ExplosionAfterGlow,
-    ExplosionRadius,
-    MissileSpeed,
-}
-
-impl Skill {
-    /// Get all available skills
-    pub fn all() -> Vec<Skill> {
-        vec![
-            Skill::ExplosionSpeed,
-            Skill::ExplosionAfterGlow,
-            Skill::ExplosionRadius,
-            Skill::MissileSpeed,
-        ]
-    }
-
-    /// Get a random subset of skills
-    pub fn random_subset(count: usize) -> Vec<Skill> {
-        let mut skills = Self::all();
-        let mut rng = rand::rng();
-        skills.shuffle(&mut rng);
-        skills.truncate(count);
-        skills
-    }
-
-    /// Get the name of the skill
-    pub fn name(&self) -> &'static str {
-        match self {
-            Skill::ExplosionSpeed => "Explosion Speed",
-            Skill::ExplosionAfterGlow => "Explosion After Glow",
-            Skill::ExplosionRadius => "Explosion Radius",
-            Skill::MissileSpeed => "Missile Speed",
-        }
-    }
+use std::collections::BTreeMap;
 
-    /// Get the description of the skill
-    pub fn description(&self) -> &'static str {
-        match self {
-            Skill::ExplosionSpeed => "Explosions grow 20% faster per level",
-            Skill::ExplosionAfterGlow => "Explosions last 50% longer per level",
-            Skill::ExplosionRadius => "Explosions are 15% larger per level",
-            Skill::MissileSpeed => "Missiles are 15% faster per level",
-        }
-    }
-}
+use crate::game::constants::{
+    BUNKER_MAGAZINE_SIZE, BUNKER_MAX_ARMOUR, BUNKER_RELOAD_TIME, EXPLOSION_AFTER_GLOW, EXPLOSION_GROWTH_RATE,
+    EXPLOSION_MAX_RADIUS, MISSILE_SPEED,
+};
+use crate::game::skills::{ModifierKind, SkillBook, Stat};
 
 /// Player struct that holds the levels of the skills of the player
 pub struct Player {
-    // Skill levels
     player_level: u32,
     experience: f32,
-    explosion_speed_level: u32,
-    explosion_after_glow_level: u32,
-    explosion_radius_level: u32,
-    missile_speed_level: u32,
+    // Skill levels by skill id, skills that were never leveled up are missing
+    skill_levels: BTreeMap<String, u32>,
 }
 
 impl Player {
@@ -68,35 +20,71 @@ impl Player {
         Self {
             player_level: 0,
             experience: 0.0,
-            explosion_speed_level: 0,
-            explosion_after_glow_level: 0,
-            explosion_radius_level: 0,
-            missile_speed_level: 0,
+            skill_levels: BTreeMap::new(),
         }
     }
 
-    /// Get the explosion growth rate multiplier based on the explosion speed skill level
-    /// Explosions grow 20% faster per level
+    /// Apply the modifiers of all skills the player has learned to the base value of a stat
+    pub fn stat(&self, stat: Stat, base: f32) -> f32 {
+        let mut percent = 0.0;
+        let mut flat = 0.0;
+        for (id, &level) in &self.skill_levels {
+            let Some(skill) = SkillBook::get().skill(id) else {
+                continue;
+            };
+            for modifier in skill.modifiers.iter().filter(|modifier| modifier.stat == stat) {
+                match modifier.kind {
+                    ModifierKind::Percent => percent += modifier.per_level * level as f32,
+                    ModifierKind::Flat => flat += modifier.per_level * level as f32,
+                }
+            }
+        }
+        base * (1.0 + percent) + flat
+    }
+
+    /// Get the explosion growth rate based on the player's skills
     pub fn get_explosion_growth_rate(&self) -> f32 {
-        EXPLOSION_GROWTH_RATE * (1.0 + 0.2 * self.explosion_speed_level as f32)
+        self.stat(Stat::ExplosionGrowthRate, EXPLOSION_GROWTH_RATE)
     }
 
-    /// Get the explosion static duration multiplier based on the explosion after glow skill level
-    /// Static duration grows by 20% per level
+    /// Get the explosion static duration based on the player's skills
     pub fn get_explosion_static_duration(&self) -> f32 {
-        EXPLOSION_AFTER_GLOW * (1.0 + 0.50 * self.explosion_after_glow_level as f32) // Base value 0.05 from Explosion::new
+        self.stat(Stat::ExplosionStaticDuration, EXPLOSION_AFTER_GLOW)
     }
 
-    /// Get the explosion max radius multiplier based on the explosion radius skill level
-    /// Max radius is 20% bigger per level
+    /// Get the explosion max radius based on the player's skills
     pub fn get_explosion_max_radius(&self) -> f32 {
-        EXPLOSION_MAX_RADIUS * (1.0 + 0.15 * self.explosion_radius_level as f32)
+        self.stat(Stat::ExplosionMaxRadius, EXPLOSION_MAX_RADIUS)
     }
 
-    /// Get the missile speed multiplier based on the missile speed skill level
-    /// Missiles are 20% faster per level
+    /// Get the missile speed based on the player's skills
     pub fn get_missile_speed(&self) -> f32 {
-        MISSILE_SPEED * (1.0 + 0.15 * self.missile_speed_level as f32)
+        self.stat(Stat::MissileSpeed, MISSILE_SPEED)
+    }
+
+    /// Get the number of warheads player missiles split into, 1 means they don't split
+    pub fn get_warheads(&self) -> u32 {
+        self.stat(Stat::Warheads, 1.0).round().max(1.0) as u32
+    }
+
+    /// Get the rebuild speed of destroyed bunkers relative to the base rebuild time
+    pub fn get_rebuild_speed(&self) -> f32 {
+        self.stat(Stat::RebuildSpeed, 1.0)
+    }
+
+    /// Get the share of the damage to bunkers that is blocked by armour
+    pub fn get_armour(&self) -> f32 {
+        self.stat(Stat::Armour, 0.0).clamp(0.0, BUNKER_MAX_ARMOUR)
+    }
+
+    /// Get the number of rounds a bunker can hold
+    pub fn get_magazine_size(&self) -> u32 {
+        self.stat(Stat::MagazineSize, BUNKER_MAGAZINE_SIZE as f32).round().max(1.0) as u32
+    }
+
+    /// Get the time a bunker needs to load a single round
+    pub fn get_reload_time(&self) -> f32 {
+        BUNKER_RELOAD_TIME / self.stat(Stat::ReloadSpeed, 1.0)
     }
 
     pub fn player_level(&self) -> u32 {
@@ -122,25 +110,25 @@ impl Player {
         stars
     }
 
+    /// Spend experience on something other than stars.
+    /// Returns false, without spending anything, if the player doesn't have enough experience.
+    pub fn spend_experience(&mut self, amount: f32) -> bool {
+        if self.experience < amount {
+            return false;
+        }
+        self.experience -= amount;
+        true
+    }
+
     /// Level up a specific skill
-    pub fn level_up_skill(&mut self, skill: Skill) {
+    pub fn level_up_skill(&mut self, skill: &str) {
         self.player_level += 1;
-        match skill {
-            Skill::ExplosionSpeed => self.explosion_speed_level += 1,
-            Skill::ExplosionAfterGlow => self.explosion_after_glow_level += 1,
-            Skill::ExplosionRadius => self.explosion_radius_level += 1,
-            Skill::MissileSpeed => self.missile_speed_level += 1,
-        }
+        *self.skill_levels.entry(skill.to_string()).or_insert(0) += 1;
     }
 
     /// Get the current level of a specific skill
-    pub fn get_skill_level(&self, skill: Skill) -> u32 {
-        match skill {
-            Skill::ExplosionSpeed => self.explosion_speed_level,
-            Skill::ExplosionAfterGlow => self.explosion_after_glow_level,
-            Skill::ExplosionRadius => self.explosion_radius_level,
-            Skill::MissileSpeed => self.missile_speed_level,
-        }
+    pub fn get_skill_level(&self, skill: &str) -> u32 {
+        self.skill_levels.get(skill).copied().unwrap_or(0)
     }
 
     /// Get the current experience
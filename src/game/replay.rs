@@ -0,0 +1,240 @@
+use macroquad::prelude::Vec2;
+
+use crate::game::difficulty::Difficulty;
+use crate::game::waves::GameMode;
+use crate::game::world::Command;
+
+const HEADER: &str = "MSR1";
+
+/// A command that was given to the world right before the given tick
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReplayEvent {
+  pub tick: u64,
+  pub command: Command,
+}
+
+/// Everything needed to replay a run: the seed, mode and difficulty of the world and all player commands.
+///
+/// The text format has a header line followed by one line per command:
+/// ```text
+/// MSR1 <seed> <mode> <difficulty>
+/// <tick> F <x bits> <y bits>
+/// <tick> B <bunker index> <x bits> <y bits>
+/// <tick> S <skill index>
+/// <tick> R <bunker index>
+/// ```
+/// Positions are stored as hex encoded `f32` bits, so the replay is bit exact.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+  pub seed: u64,
+  pub mode: GameMode,
+  pub difficulty: Difficulty,
+  pub events: Vec<ReplayEvent>,
+}
+
+impl Replay {
+  pub fn new(seed: u64, mode: GameMode, difficulty: Difficulty) -> Self {
+    Self {
+      seed,
+      mode,
+      difficulty,
+      events: Vec::new(),
+    }
+  }
+
+  pub fn record(&mut self, tick: u64, command: Command) {
+    self.events.push(ReplayEvent { tick, command });
+  }
+
+  pub fn parse(text: &str) -> Result<Self, String> {
+    let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+
+    let header = lines.next().ok_or("Replay is empty")?;
+    let (seed, mode, difficulty) = match header.split_whitespace().collect::<Vec<_>>().as_slice() {
+      [HEADER, seed, mode, difficulty] => {
+        let seed = seed.parse().map_err(|_| format!("Invalid seed '{seed}'"))?;
+        let mode = GameMode::from_id(mode).ok_or(format!("Invalid mode '{mode}'"))?;
+        let difficulty = Difficulty::from_id(difficulty).ok_or(format!("Invalid difficulty '{difficulty}'"))?;
+        (seed, mode, difficulty)
+      }
+      _ => return Err(format!("Invalid replay header '{header}'")),
+    };
+
+    let mut replay = Self::new(seed, mode, difficulty);
+    for line in lines {
+      let event = Self::parse_event(line).ok_or_else(|| format!("Invalid replay line '{line}'"))?;
+      replay.events.push(event);
+    }
+    Ok(replay)
+  }
+
+  fn parse_event(line: &str) -> Option<ReplayEvent> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    let tick = parts.first()?.parse().ok()?;
+    let command = match parts[1..] {
+      ["F", x, y] => Command::Fire(Vec2::new(parse_f32_bits(x)?, parse_f32_bits(y)?)),
+      ["B", index, x, y] => Command::FireFrom(index.parse().ok()?, Vec2::new(parse_f32_bits(x)?, parse_f32_bits(y)?)),
+      ["S", index] => Command::PickSkill(index.parse().ok()?),
+      ["R", index] => Command::RebuildBunker(index.parse().ok()?),
+      _ => return None,
+    };
+    Some(ReplayEvent { tick, command })
+  }
+
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn load(path: &str) -> Result<Self, String> {
+    let text = std::fs::read_to_string(path).map_err(|err| format!("Could not read replay '{path}': {err}"))?;
+    Self::parse(&text)
+  }
+
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn save(&self, path: &str) -> Result<(), String> {
+    std::fs::write(path, self.to_string()).map_err(|err| format!("Could not write replay '{path}': {err}"))
+  }
+}
+
+impl std::fmt::Display for Replay {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    writeln!(f, "{HEADER} {} {} {}", self.seed, self.mode.id(), self.difficulty.id())?;
+    for ReplayEvent { tick, command } in &self.events {
+      match command {
+        Command::Fire(pos) => writeln!(f, "{tick} F {:08x} {:08x}", pos.x.to_bits(), pos.y.to_bits())?,
+        Command::FireFrom(index, pos) => {
+          writeln!(f, "{tick} B {index} {:08x} {:08x}", pos.x.to_bits(), pos.y.to_bits())?;
+        }
+        Command::PickSkill(index) => writeln!(f, "{tick} S {index}")?,
+        Command::RebuildBunker(index) => writeln!(f, "{tick} R {index}")?,
+      }
+    }
+    Ok(())
+  }
+}
+
+fn parse_f32_bits(text: &str) -> Option<f32> {
+  u32::from_str_radix(text, 16).ok().map(f32::from_bits)
+}
+
+/// Feeds the commands of a replay back into the world tick by tick
+pub struct ReplayPlayer {
+  replay: Replay,
+  next_event: usize,
+}
+
+impl ReplayPlayer {
+  pub fn new(replay: Replay) -> Self {
+    Self { replay, next_event: 0 }
+  }
+
+  pub fn seed(&self) -> u64 {
+    self.replay.seed
+  }
+
+  pub fn mode(&self) -> GameMode {
+    self.replay.mode
+  }
+
+  pub fn difficulty(&self) -> Difficulty {
+    self.replay.difficulty
+  }
+
+  /// All commands that have to be applied before the given tick
+  pub fn commands_for_tick(&mut self, tick: u64) -> Vec<Command> {
+    let mut commands = Vec::new();
+    while let Some(event) = self.replay.events.get(self.next_event) {
+      if event.tick > tick {
+        break;
+      }
+      commands.push(event.command);
+      self.next_event += 1;
+    }
+    commands
+  }
+
+  pub fn is_finished(&self) -> bool {
+    self.next_event >= self.replay.events.len()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::game::constants::SIMULATION_DT;
+  use crate::game::world::World;
+
+  // Play a run the way a player would and record it like the game does
+  fn record_run(seed: u64, seconds: f32) -> (Replay, World) {
+    let mut replay = Replay::new(seed, GameMode::Waves, Difficulty::Insane);
+    let mut world = World::new(seed, replay.mode, replay.difficulty);
+    while world.game_time() < seconds && !world.is_game_over() {
+      let tick = world.tick();
+      let mut commands = Vec::new();
+      if world.is_skill_selection_active() {
+        commands.push(Command::PickSkill(tick as usize % world.skill_options().len().max(1)));
+      } else if tick.is_multiple_of(45) {
+        let target = Vec2::new((tick as f32 * 0.37).sin() * 350.0, -150.0 + (tick as f32 * 0.11).cos() * 150.0);
+        let bunker_idx = (tick / 45) as usize % 4;
+        commands.push(if bunker_idx < 3 { Command::FireFrom(bunker_idx, target) } else { Command::Fire(target) });
+      }
+      if let Some(destroyed) = world.bunkers().iter().position(|bunker| !bunker.active) {
+        commands.push(Command::RebuildBunker(destroyed));
+      }
+      for command in &commands {
+        replay.record(tick, *command);
+      }
+      world.step(SIMULATION_DT, &commands);
+    }
+    (replay, world)
+  }
+
+  fn play_back(replay: Replay, ticks: u64) -> World {
+    let mut player = ReplayPlayer::new(replay);
+    let mut world = World::new(player.seed(), player.mode(), player.difficulty());
+    while world.tick() < ticks && !world.is_game_over() {
+      let commands = player.commands_for_tick(world.tick());
+      world.step(SIMULATION_DT, &commands);
+    }
+    assert!(player.is_finished());
+    world
+  }
+
+  #[test]
+  fn replays_survive_the_text_format() {
+    let (replay, _) = record_run(3, 60.0);
+    for command in ["F", "B", "S"] {
+      assert!(replay.to_string().contains(&format!(" {command} ")), "no {command} command recorded");
+    }
+    let mut replay_with_rebuild = replay.clone();
+    replay_with_rebuild.record(1_000_000, Command::RebuildBunker(2));
+    for replay in [replay, replay_with_rebuild] {
+      assert_eq!(Replay::parse(&replay.to_string()), Ok(replay));
+    }
+  }
+
+  #[test]
+  fn broken_replays_are_rejected() {
+    assert!(Replay::parse("").is_err());
+    assert!(Replay::parse("MSR1 5 endless normal").is_ok());
+    assert!(Replay::parse("MSR1 5 endless").is_err());
+    assert!(Replay::parse("MSR1 5 endless hardest").is_err());
+    assert!(Replay::parse("MSR1 5 endless normal\n12 F zz 0").is_err());
+  }
+
+  #[test]
+  fn playing_back_a_replay_reproduces_the_run() {
+    let (replay, recorded) = record_run(11, 180.0);
+    assert!(recorded.stats().missiles_intercepted > 0 && recorded.stats().bunkers_rebuilt > 0);
+
+    let replayed = play_back(Replay::parse(&replay.to_string()).unwrap(), recorded.tick());
+    assert_eq!(replayed.tick(), recorded.tick());
+    assert_eq!(replayed.is_game_over(), recorded.is_game_over());
+    assert_eq!(replayed.game_time(), recorded.game_time());
+    assert_eq!(replayed.player().player_level(), recorded.player().player_level());
+    assert_eq!(replayed.player().experience(), recorded.player().experience());
+    assert_eq!(replayed.stats(), recorded.stats());
+    assert_eq!(replayed.wave().map(|wave| wave.number), recorded.wave().map(|wave| wave.number));
+    let bunkers = |world: &World| world.bunkers().iter().map(|bunker| (bunker.active, bunker.health)).collect::<Vec<_>>();
+    assert_eq!(bunkers(&replayed), bunkers(&recorded));
+    let missiles = |world: &World| world.missiles().iter().map(|missile| missile.current_pos).collect::<Vec<_>>();
+    assert_eq!(missiles(&replayed), missiles(&recorded));
+  }
+}
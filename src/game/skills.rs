@@ -0,0 +1,157 @@
+use lazy_static::lazy_static;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use serde::Deserialize;
+
+use crate::game::player::Player;
+
+lazy_static! {
+  static ref SKILL_BOOK: SkillBook = SkillBook::parse(include_str!("../../assets/skills.ron")).unwrap_or_else(|err| panic!("{err}"));
+}
+
+/// A value of the player that skills can modify
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Stat {
+  ExplosionGrowthRate,
+  ExplosionStaticDuration,
+  ExplosionMaxRadius,
+  MissileSpeed,
+  Warheads,
+  RebuildSpeed,
+  Armour,
+  MagazineSize,
+  ReloadSpeed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum ModifierKind {
+  /// Adds `per_level` times the base value per level
+  Percent,
+  /// Adds `per_level` per level
+  Flat,
+}
+
+/// How a skill changes a stat per level
+#[derive(Debug, Clone, Deserialize)]
+pub struct Modifier {
+  pub stat: Stat,
+  pub kind: ModifierKind,
+  pub per_level: f32,
+}
+
+/// A skill that has to reach `level` before another skill is offered
+#[derive(Debug, Clone, Deserialize)]
+pub struct Prerequisite {
+  pub skill: String,
+  pub level: u32,
+}
+
+/// Definition of a skill as written in `assets/skills.ron`
+#[derive(Debug, Clone, Deserialize)]
+pub struct SkillDef {
+  pub id: String,
+  pub name: String,
+  pub description: String,
+  pub max_level: u32,
+  #[serde(default)]
+  pub prerequisites: Vec<Prerequisite>,
+  pub modifiers: Vec<Modifier>,
+}
+
+impl SkillDef {
+  /// Whether the player can currently level up this skill
+  pub fn is_available_for(&self, player: &Player) -> bool {
+    player.get_skill_level(&self.id) < self.max_level
+      && self
+        .prerequisites
+        .iter()
+        .all(|prerequisite| player.get_skill_level(&prerequisite.skill) >= prerequisite.level)
+  }
+}
+
+/// All skills of the game
+pub struct SkillBook {
+  skills: Vec<SkillDef>,
+}
+
+impl SkillBook {
+  pub fn get() -> &'static SkillBook {
+    &SKILL_BOOK
+  }
+
+  pub fn parse(text: &str) -> Result<Self, String> {
+    let skills: Vec<SkillDef> = ron::from_str(text).map_err(|err| format!("Invalid skill definitions: {err}"))?;
+
+    for skill in &skills {
+      if skills.iter().filter(|other| other.id == skill.id).count() > 1 {
+        return Err(format!("Skill '{}' is defined more than once", skill.id));
+      }
+      if skill.max_level == 0 {
+        return Err(format!("Skill '{}' can never be leveled up, its max_level is 0", skill.id));
+      }
+      if skill.modifiers.is_empty() {
+        return Err(format!("Skill '{}' has no modifiers", skill.id));
+      }
+      for prerequisite in &skill.prerequisites {
+        if !skills.iter().any(|other| other.id == prerequisite.skill) {
+          return Err(format!("Skill '{}' requires unknown skill '{}'", skill.id, prerequisite.skill));
+        }
+      }
+    }
+
+    Ok(Self { skills })
+  }
+
+  pub fn skills(&self) -> &[SkillDef] {
+    &self.skills
+  }
+
+  pub fn skill(&self, id: &str) -> Option<&SkillDef> {
+    self.skills.iter().find(|skill| skill.id == id)
+  }
+
+  /// Display name of a skill, falling back to the id for unknown skills
+  pub fn name<'a>(&'a self, id: &'a str) -> &'a str {
+    self.skill(id).map_or(id, |skill| skill.name.as_str())
+  }
+
+  /// Get the ids of a random subset of the skills the player can level up
+  pub fn random_subset(&self, player: &Player, count: usize, rng: &mut impl Rng) -> Vec<String> {
+    let mut skills: Vec<&SkillDef> = self.skills.iter().filter(|skill| skill.is_available_for(player)).collect();
+    skills.shuffle(rng);
+    skills.into_iter().take(count).map(|skill| skill.id.clone()).collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const DEFINITIONS: &str = include_str!("../../assets/skills.ron");
+
+  #[test]
+  fn shipped_definitions_are_valid() {
+    let book = SkillBook::parse(DEFINITIONS).unwrap();
+    assert!(!book.skills().is_empty());
+  }
+
+  #[test]
+  fn skills_without_levels_are_rejected() {
+    let broken = DEFINITIONS.replacen("max_level: 10", "max_level: 0", 1);
+    assert_ne!(broken, DEFINITIONS);
+    let err = SkillBook::parse(&broken).err().unwrap();
+    assert!(err.contains("max_level"), "{err}");
+  }
+
+  #[test]
+  fn skills_without_modifiers_are_rejected() {
+    let broken = DEFINITIONS.replacen(
+      "modifiers: [(stat: ExplosionGrowthRate, kind: Percent, per_level: 0.2)]",
+      "modifiers: []",
+      1,
+    );
+    assert_ne!(broken, DEFINITIONS);
+    let err = SkillBook::parse(&broken).err().unwrap();
+    assert!(err.contains("no modifiers"), "{err}");
+  }
+}
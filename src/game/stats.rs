@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+
+/// Experience earned while the player had a certain set of skills
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildExperience {
+  pub player_level: u32,
+  /// Id of the skill whose level up started this build, `None` for the initial build
+  pub skill: Option<String>,
+  pub experience: f32,
+}
+
+/// A bunker that was destroyed during the run
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BunkerLoss {
+  pub bunker_idx: usize,
+  pub game_time: f32,
+}
+
+/// Counters collected over a single run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunStats {
+  pub missiles_intercepted: u32,
+  pub carriers_destroyed: u32,
+  pub missiles_fired: u32,
+  /// Player missiles whose explosion destroyed at least one enemy missile, directly or through a chain reaction
+  pub missiles_hit: u32,
+  /// Most enemy missiles destroyed by a single chain of explosions
+  pub longest_chain: u32,
+  /// Height above the bunkers of the highest enemy missile that was destroyed
+  pub highest_kill_altitude: f32,
+  pub experience_by_build: Vec<BuildExperience>,
+  pub bunkers_lost: Vec<BunkerLoss>,
+  pub bunkers_rebuilt: u32,
+}
+
+impl RunStats {
+  pub fn new() -> Self {
+    Self {
+      missiles_intercepted: 0,
+      carriers_destroyed: 0,
+      missiles_fired: 0,
+      missiles_hit: 0,
+      longest_chain: 0,
+      highest_kill_altitude: 0.0,
+      experience_by_build: vec![BuildExperience {
+        player_level: 0,
+        skill: None,
+        experience: 0.0,
+      }],
+      bunkers_lost: Vec::new(),
+      bunkers_rebuilt: 0,
+    }
+  }
+
+  /// Share of the fired player missiles that destroyed something, between 0.0 and 1.0
+  pub fn accuracy(&self) -> f32 {
+    if self.missiles_fired == 0 {
+      0.0
+    } else {
+      self.missiles_hit as f32 / self.missiles_fired as f32
+    }
+  }
+
+  pub fn add_experience(&mut self, experience: f32) {
+    if let Some(build) = self.experience_by_build.last_mut() {
+      build.experience += experience;
+    }
+  }
+
+  /// Start counting experience for a new build after the player leveled up `skill`
+  pub fn start_build(&mut self, player_level: u32, skill: &str) {
+    self.experience_by_build.push(BuildExperience {
+      player_level,
+      skill: Some(skill.to_string()),
+      experience: 0.0,
+    });
+  }
+}
+
+impl Default for RunStats {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// A sequence of explosions that started with a single detonation
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Chain {
+  pub kills: u32,
+  pub fired_by_player: bool,
+}
+
+/// The chains that are still going on. Explosions and missiles refer to their chain by index,
+/// and the slots of finished chains are reused, so a long run doesn't keep every chain it ever saw.
+#[derive(Debug, Clone, Default)]
+pub struct Chains(Vec<Option<Chain>>);
+
+impl Chains {
+  /// Start a new chain and return its index
+  pub fn start(&mut self, fired_by_player: bool) -> usize {
+    let chain = Some(Chain {
+      kills: 0,
+      fired_by_player,
+    });
+    match self.0.iter().position(Option::is_none) {
+      Some(index) => {
+        self.0[index] = chain;
+        index
+      }
+      None => {
+        self.0.push(chain);
+        self.0.len() - 1
+      }
+    }
+  }
+
+  /// Free the slots of all chains that are not in `referenced` anymore
+  pub fn release_unreferenced(&mut self, referenced: impl IntoIterator<Item = usize>) {
+    let mut in_use = vec![false; self.0.len()];
+    for index in referenced {
+      in_use[index] = true;
+    }
+    for (chain, in_use) in self.0.iter_mut().zip(in_use) {
+      if !in_use {
+        *chain = None;
+      }
+    }
+  }
+
+  /// Number of slots, including the free ones
+  pub fn capacity(&self) -> usize {
+    self.0.len()
+  }
+}
+
+impl std::ops::Index<usize> for Chains {
+  type Output = Chain;
+
+  fn index(&self, index: usize) -> &Chain {
+    self.0[index].as_ref().expect("Chain was released while it was still in use")
+  }
+}
+
+impl std::ops::IndexMut<usize> for Chains {
+  fn index_mut(&mut self, index: usize) -> &mut Chain {
+    self.0[index].as_mut().expect("Chain was released while it was still in use")
+  }
+}
@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+
+use crate::game::constants::get_wave_missile_budget;
+
+/// How enemies arrive over the course of a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum GameMode {
+  /// Enemies keep coming and get faster and more frequent over time
+  #[default]
+  Endless,
+  /// Numbered waves with a fixed number of missiles and a short break in between
+  Waves,
+}
+
+impl GameMode {
+  pub fn name(self) -> &'static str {
+    match self {
+      GameMode::Endless => "Endless",
+      GameMode::Waves => "Waves",
+    }
+  }
+
+  pub fn next(self) -> Self {
+    match self {
+      GameMode::Endless => GameMode::Waves,
+      GameMode::Waves => GameMode::Endless,
+    }
+  }
+
+  /// Name of the mode in replay files
+  pub fn id(self) -> &'static str {
+    match self {
+      GameMode::Endless => "endless",
+      GameMode::Waves => "waves",
+    }
+  }
+
+  pub fn from_id(id: &str) -> Option<Self> {
+    [GameMode::Endless, GameMode::Waves].into_iter().find(|mode| mode.id() == id)
+  }
+}
+
+/// What the player got for surviving a wave
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WaveBonus {
+  pub surviving_bunkers: u32,
+  pub unused_ammo: u32,
+  pub experience: f32,
+  pub rebuilt_bunker: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WavePhase {
+  /// Enemies are coming, `missiles_left` of them still have to spawn
+  Attack { missiles_left: u32 },
+  /// The wave was survived and the next one starts in `time_left` seconds
+  Break { time_left: f32, bonus: WaveBonus },
+}
+
+/// Progress through the waves of a run in wave mode
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Wave {
+  pub number: u32,
+  pub phase: WavePhase,
+}
+
+impl Wave {
+  pub fn first() -> Self {
+    Self::start(1)
+  }
+
+  pub fn start(number: u32) -> Self {
+    Self {
+      number,
+      phase: WavePhase::Attack {
+        missiles_left: get_wave_missile_budget(number),
+      },
+    }
+  }
+
+  pub fn is_break(&self) -> bool {
+    matches!(self.phase, WavePhase::Break { .. })
+  }
+}
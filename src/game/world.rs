@@ -0,0 +1,997 @@
+use ::rand::seq::SliceRandom;
+use ::rand::{Rng, SeedableRng};
+use macroquad::prelude::Vec2;
+use rand_chacha::ChaCha8Rng;
+
+use crate::game::bunker::Bunker;
+use crate::game::carrier::{Carrier, CarrierKind};
+use crate::game::constants::*;
+use crate::game::difficulty::{Difficulty, DifficultyCurves};
+use crate::game::explosion::{Explosion, ExplosionParams};
+use crate::game::missile::{EnemyKind, Missile, MissileBehaviour, MissileKind, smart_bomb_heading};
+use crate::game::player::Player;
+use crate::game::skills::SkillBook;
+use crate::game::events::GameEvent;
+use crate::game::star::Star;
+use crate::game::stats::{BunkerLoss, Chain, Chains, RunStats};
+use crate::game::waves::{GameMode, Wave, WaveBonus, WavePhase};
+
+/// An input the player can give to the simulation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+  /// Fire a missile from the closest bunker with a round loaded at the given world position
+  Fire(Vec2),
+  /// Fire a missile from the bunker with the given index, if it has a round loaded
+  FireFrom(usize, Vec2),
+  /// Pick the n-th skill of the currently offered skills
+  PickSkill(usize),
+  /// Spend experience to rebuild the destroyed bunker with the given index
+  RebuildBunker(usize),
+}
+
+/// Random number generator used for all gameplay randomness.
+/// Unlike `StdRng` its output is fixed across rand versions and platforms, so seeds and replays stay valid.
+pub type GameRng = ChaCha8Rng;
+
+/// The complete game simulation without any rendering or input handling.
+/// It only advances when `step` is called, so it can run without a window.
+/// Two worlds with the same seed that receive the same commands play out identically.
+pub struct World {
+  seed: u64,
+  mode: GameMode,
+  difficulty: Difficulty,
+  rng: GameRng,
+  tick: u64,
+  bunkers: Vec<Bunker>,
+  missiles: Vec<Missile>,
+  explosions: Vec<Explosion>,
+  stars: Vec<Star>,
+  carriers: Vec<Carrier>,
+  time_until_next_missile_spawn: f32,
+  time_until_next_carrier_spawn: f32,
+  wave: Wave, // Only used in wave mode
+  game_over: bool,
+  game_time: f32,
+  player: Player,
+  level_ups_left: usize,
+  skill_options: Vec<String>,
+  stats: RunStats,
+  chains: Chains,
+  events: Vec<GameEvent>, // Events of the last step
+}
+
+impl World {
+  pub fn new(seed: u64, mode: GameMode, difficulty: Difficulty) -> Self {
+    // Create three bunkers at the bottom of the screen
+    let bunkers = vec![
+      Bunker::new(Vec2::new(-200.0, 280.0 - BUNKER_HEIGHT)),
+      Bunker::new(Vec2::new(0.0, 280.0 - BUNKER_HEIGHT)),
+      Bunker::new(Vec2::new(200.0, 280.0 - BUNKER_HEIGHT)),
+    ];
+
+    Self {
+      seed,
+      mode,
+      difficulty,
+      rng: GameRng::seed_from_u64(seed),
+      tick: 0,
+      bunkers,
+      missiles: Vec::new(),
+      explosions: Vec::new(),
+      stars: Vec::new(),
+      carriers: Vec::new(),
+      time_until_next_missile_spawn: INITIAL_SPAWN_TIME,
+      time_until_next_carrier_spawn: CARRIER_FIRST_SPAWN_TIME,
+      wave: Wave::first(),
+      game_over: false,
+      game_time: 0.0,
+      player: Player::new(),
+      level_ups_left: 0,
+      skill_options: Vec::new(),
+      stats: RunStats::new(),
+      chains: Chains::default(),
+      events: Vec::new(),
+    }
+  }
+
+  /// The seed this world was created with
+  pub fn seed(&self) -> u64 {
+    self.seed
+  }
+
+  pub fn mode(&self) -> GameMode {
+    self.mode
+  }
+
+  pub fn difficulty(&self) -> Difficulty {
+    self.difficulty
+  }
+
+  /// The current wave in wave mode, `None` in endless mode
+  pub fn wave(&self) -> Option<&Wave> {
+    match self.mode {
+      GameMode::Endless => None,
+      GameMode::Waves => Some(&self.wave),
+    }
+  }
+
+  /// Number of steps the world has been advanced
+  pub fn tick(&self) -> u64 {
+    self.tick
+  }
+
+  pub fn bunkers(&self) -> &[Bunker] {
+    &self.bunkers
+  }
+
+  pub fn missiles(&self) -> &[Missile] {
+    &self.missiles
+  }
+
+  pub fn explosions(&self) -> &[Explosion] {
+    &self.explosions
+  }
+
+  pub fn stars(&self) -> &[Star] {
+    &self.stars
+  }
+
+  pub fn carriers(&self) -> &[Carrier] {
+    &self.carriers
+  }
+
+  pub fn player(&self) -> &Player {
+    &self.player
+  }
+
+  pub fn game_time(&self) -> f32 {
+    self.game_time
+  }
+
+  pub fn stats(&self) -> &RunStats {
+    &self.stats
+  }
+
+  pub fn is_game_over(&self) -> bool {
+    self.game_over
+  }
+
+  /// What happened during the last call to `step`
+  pub fn events(&self) -> &[GameEvent] {
+    &self.events
+  }
+
+  /// Ids of the skills currently offered to the player, empty if no level up is pending
+  pub fn skill_options(&self) -> &[String] {
+    &self.skill_options
+  }
+
+  pub fn is_skill_selection_active(&self) -> bool {
+    self.level_ups_left > 0
+  }
+
+  /// Advance the simulation by `dt` seconds after applying the given commands
+  pub fn step(&mut self, dt: f32, commands: &[Command]) {
+    self.events.clear();
+    if self.game_over {
+      return;
+    }
+
+    self.tick += 1;
+
+    // Remember where everything was, so rendering can interpolate between ticks
+    for missile in &mut self.missiles {
+      missile.prev_pos = missile.current_pos;
+    }
+    for carrier in &mut self.carriers {
+      carrier.prev_pos = carrier.pos;
+    }
+
+    // Offer new skills if there are level ups available
+    self.show_skill_selection_menu();
+
+    for command in commands {
+      self.apply_command(*command);
+    }
+
+    // The simulation is paused while the player chooses a skill
+    if self.is_skill_selection_active() {
+      return;
+    }
+
+    self.game_time += dt;
+
+    match self.mode {
+      GameMode::Endless => self.update_endless_spawns(dt),
+      GameMode::Waves => self.update_wave(dt),
+    }
+
+    // Update game state
+    self.update_carriers(dt);
+    self.update_missiles(dt);
+    self.update_explosions(dt);
+    self.update_bunkers(dt);
+    self.check_game_over();
+    self.release_chains();
+  }
+
+  // Chains are over once neither an explosion nor a missile belongs to them anymore
+  fn release_chains(&mut self) {
+    let explosion_chains = self.explosions.iter().map(|explosion| explosion.chain);
+    let missile_chains = self.missiles.iter().filter_map(|missile| missile.chain);
+    self.chains.release_unreferenced(explosion_chains.chain(missile_chains));
+  }
+
+  // Enemies spawn ever faster the longer the run lasts
+  fn update_endless_spawns(&mut self, dt: f32) {
+    // Spawn enemy missiles
+    self.time_until_next_missile_spawn -= dt;
+
+    if self.time_until_next_missile_spawn <= 0.0 {
+      self.spawn_enemy_missile();
+
+      // Get base spawn interval
+      let spawn_interval = self.curves().spawn_interval.sample(self.game_time);
+
+      // Randomize by ±50%
+      let random_factor = self.rng.random_range(0.5..1.5);
+      self.time_until_next_missile_spawn = spawn_interval * random_factor;
+    }
+
+    // Spawn carriers
+    self.time_until_next_carrier_spawn -= dt;
+    if self.time_until_next_carrier_spawn <= 0.0 {
+      self.spawn_carrier();
+      let random_factor = self.rng.random_range(0.75..1.25);
+      self.time_until_next_carrier_spawn = self.curves().carrier_interval.sample(self.game_time) * random_factor;
+    }
+  }
+
+  // Spawn the missiles of the current wave and move on to the next wave once it was survived
+  fn update_wave(&mut self, dt: f32) {
+    match &mut self.wave.phase {
+      WavePhase::Attack { missiles_left } if *missiles_left > 0 => {
+        self.time_until_next_missile_spawn -= dt;
+        if self.time_until_next_missile_spawn <= 0.0 {
+          *missiles_left -= 1;
+          self.spawn_enemy_missile();
+
+          let difficulty_time = self.difficulty_time();
+          let spawn_interval = self.curves().spawn_interval.sample(difficulty_time) * WAVE_SPAWN_INTERVAL_SCALE;
+          let random_factor = self.rng.random_range(0.5..1.5);
+          self.time_until_next_missile_spawn = spawn_interval * random_factor;
+        }
+      }
+      WavePhase::Attack { .. } => {
+        // The wave is survived once every enemy of it is gone
+        let enemies_left = self.carriers.len() + self.missiles.iter().filter(|m| !m.is_player_missile()).count();
+        if enemies_left == 0 {
+          self.finish_wave();
+        }
+      }
+      WavePhase::Break { time_left, .. } => {
+        *time_left -= dt;
+        if *time_left <= 0.0 {
+          self.start_wave(self.wave.number + 1);
+        }
+      }
+    }
+  }
+
+  // Award the bonus for the survived wave and rebuild one destroyed bunker
+  fn finish_wave(&mut self) {
+    let active_bunkers = self.bunkers.iter().filter(|bunker| bunker.active);
+    let surviving_bunkers = active_bunkers.clone().count() as u32;
+    let unused_ammo = active_bunkers.map(|bunker| bunker.ammo).sum::<u32>();
+    let experience = surviving_bunkers as f32 * WAVE_BUNKER_BONUS + unused_ammo as f32 * WAVE_AMMO_BONUS;
+
+    let ruin = self.bunkers.iter_mut().find(|bunker| !bunker.active);
+    let rebuilt_bunker = ruin.is_some();
+    if let Some(bunker) = ruin {
+      bunker.reset();
+      self.stats.bunkers_rebuilt += 1;
+    }
+
+    self.award_experience(experience);
+    self.wave.phase = WavePhase::Break {
+      time_left: WAVE_BREAK_TIME,
+      bonus: WaveBonus {
+        surviving_bunkers,
+        unused_ammo,
+        experience,
+        rebuilt_bunker,
+      },
+    };
+  }
+
+  // Refill the magazines and let the next wave come in
+  fn start_wave(&mut self, number: u32) {
+    self.wave = Wave::start(number);
+    self.time_until_next_missile_spawn = INITIAL_SPAWN_TIME;
+
+    let magazine_size = self.player.get_magazine_size();
+    for bunker in self.bunkers.iter_mut().filter(|bunker| bunker.active) {
+      bunker.ammo = magazine_size;
+      bunker.reload_timer = 0.0;
+    }
+
+    if number.is_multiple_of(WAVE_CARRIER_EVERY) {
+      self.spawn_carrier();
+    }
+  }
+
+  fn curves(&self) -> &'static DifficultyCurves {
+    self.difficulty.curves()
+  }
+
+  // Time in endless mode that matches the current difficulty
+  fn difficulty_time(&self) -> f32 {
+    match self.mode {
+      GameMode::Endless => self.game_time,
+      GameMode::Waves => get_wave_difficulty_time(self.wave.number),
+    }
+  }
+
+  fn apply_command(&mut self, command: Command) {
+    match command {
+      Command::Fire(target_pos) => {
+        if !self.is_skill_selection_active() {
+          self.fire_missile(target_pos);
+        }
+      }
+      Command::FireFrom(bunker_idx, target_pos) => {
+        if !self.is_skill_selection_active() {
+          self.fire_missile_from(bunker_idx, target_pos);
+        }
+      }
+      Command::PickSkill(index) => {
+        if self.is_skill_selection_active() {
+          self.pick_skill(index);
+        }
+      }
+      Command::RebuildBunker(bunker_idx) => {
+        if !self.is_skill_selection_active() {
+          self.buy_rebuild(bunker_idx);
+        }
+      }
+    }
+  }
+
+  fn fire_missile(&mut self, target_pos: Vec2) {
+    if let Some(bunker_idx) = self.find_closest_active_bunker(target_pos) {
+      self.fire_missile_from(bunker_idx, target_pos);
+    }
+  }
+
+  fn fire_missile_from(&mut self, bunker_idx: usize, target_pos: Vec2) {
+    let Some(bunker) = self.bunkers.get_mut(bunker_idx) else {
+      return;
+    };
+    if !bunker.can_fire() {
+      return;
+    }
+    bunker.take_round();
+    self.events.push(GameEvent::MissileLaunched { pos: bunker.pos });
+
+    // Use player's missile speed skill
+    let missile_speed = self.player.get_missile_speed();
+    let behaviour = match self.player.get_warheads() {
+      1 => MissileBehaviour::Direct,
+      warheads => MissileBehaviour::Split { warheads },
+    };
+    self
+      .missiles
+      .push(Missile::new(bunker.pos, target_pos, MissileKind::Player, missile_speed).with_behaviour(behaviour));
+    self.stats.missiles_fired += 1;
+  }
+
+  fn buy_rebuild(&mut self, bunker_idx: usize) {
+    let Some(bunker) = self.bunkers.get_mut(bunker_idx) else {
+      return;
+    };
+    if !bunker.active && self.player.spend_experience(BUNKER_REBUILD_COST) {
+      bunker.reset();
+      self.stats.bunkers_rebuilt += 1;
+    }
+  }
+
+  /// Whether the player has enough experience to rebuild a destroyed bunker right away
+  pub fn can_afford_rebuild(&self) -> bool {
+    self.player.experience() >= BUNKER_REBUILD_COST
+  }
+
+  // Reload the bunkers, destroyed bunkers slowly rebuild on their own
+  fn update_bunkers(&mut self, dt: f32) {
+    let progress = dt * self.player.get_rebuild_speed() / BUNKER_REBUILD_TIME;
+    let reload_time = self.player.get_reload_time();
+    let magazine_size = self.player.get_magazine_size();
+    for bunker in &mut self.bunkers {
+      bunker.reload(dt, reload_time, magazine_size);
+      if bunker.rebuild(progress) {
+        self.stats.bunkers_rebuilt += 1;
+      }
+    }
+  }
+
+  fn pick_skill(&mut self, index: usize) {
+    if let Some(selected_skill) = self.skill_options.get(index) {
+      // Level up the selected skill
+      self.player.level_up_skill(selected_skill);
+      self.events.push(GameEvent::LevelUp);
+      self.stats.start_build(self.player.player_level(), selected_skill);
+      self.level_ups_left -= 1;
+      self.skill_options.clear();
+
+      // If there are more level ups left, show the menu again
+      self.show_skill_selection_menu();
+    }
+  }
+
+  // Calculate experience based on missile height
+  // 0 at bunker level (y=280), 100 at top of screen (y=-300)
+  fn calculate_experience_for_missile(missile_pos: Vec2) -> f32 {
+    // Normalize the y position between bunker level and top of screen
+    let bunker_level = 280.0;
+    let top_level = -300.0;
+    let height_range = bunker_level - top_level;
+
+    // Calculate normalized height (0.0 at bunker level, 1.0 at top)
+    let normalized_height = (bunker_level - missile_pos.y) / height_range;
+
+    // Scale to experience (0 at bunker level, 100 at top)
+    normalized_height * 100.0
+  }
+
+  /// Find the closest bunker that has a round loaded
+  pub fn find_closest_active_bunker(&self, target_pos: Vec2) -> Option<usize> {
+    let mut closest_idx = None;
+    let mut closest_dist = f32::MAX;
+
+    for (idx, bunker) in self.bunkers.iter().enumerate() {
+      if bunker.can_fire() {
+        let dist = bunker.pos.distance(target_pos);
+        if dist < closest_dist {
+          closest_dist = dist;
+          closest_idx = Some(idx);
+        }
+      }
+    }
+
+    closest_idx
+  }
+
+  fn spawn_enemy_missile(&mut self) {
+    // Random position at the top of the screen
+    let start_x = self.rng.random_range(-380.0..380.0);
+    let start_pos = Vec2::new(start_x, -300.0);
+    let enemy = self.choose_enemy_kind();
+
+    if let Some(missile) = self.aim_enemy_missile(start_pos, enemy) {
+      self.missiles.push(missile);
+    }
+  }
+
+  // Pick the kind of the next enemy missile, weighted by how common each kind is at the current time
+  fn choose_enemy_kind(&mut self) -> EnemyKind {
+    let weights = self.curves().enemy_weights(self.difficulty_time());
+    let total: f32 = weights.iter().map(|(_, weight)| weight).sum();
+    let mut roll = self.rng.random_range(0.0..total);
+    for (enemy, weight) in weights {
+      if roll < weight {
+        return enemy;
+      }
+      roll -= weight;
+    }
+    EnemyKind::Standard
+  }
+
+  // Create an enemy missile at `start_pos` that targets one of the active bunkers
+  fn aim_enemy_missile(&mut self, start_pos: Vec2, enemy: EnemyKind) -> Option<Missile> {
+    let target_bunker_idx = self.choose_target_bunkers(1).pop()?;
+    Some(self.enemy_missile(start_pos, enemy, target_bunker_idx))
+  }
+
+  // Pick `count` random active bunkers. Bunkers only repeat once every active bunker was picked.
+  fn choose_target_bunkers(&mut self, count: usize) -> Vec<usize> {
+    let mut active_bunkers: Vec<usize> = (self.bunkers.iter().enumerate())
+      .filter(|(_, bunker)| bunker.active)
+      .map(|(bunker_idx, _)| bunker_idx)
+      .collect();
+    active_bunkers.shuffle(&mut self.rng);
+    active_bunkers.into_iter().cycle().take(count).collect()
+  }
+
+  fn enemy_missile(&self, start_pos: Vec2, enemy: EnemyKind, target_bunker_idx: usize) -> Missile {
+    // Calculate enemy missile speed based on elapsed time
+    let speed = self.curves().missile_speed.sample(self.difficulty_time()) * enemy.speed_multiplier();
+
+    let kind = MissileKind::Enemy { enemy, target_bunker_idx };
+    Missile::new(start_pos, self.bunkers[target_bunker_idx].pos, kind, speed)
+  }
+
+  // Let a carrier enter the sky from the left or the right
+  fn spawn_carrier(&mut self) {
+    let satellite_chance = self.curves().satellite_chance.sample(self.difficulty_time()).clamp(0.0, 1.0);
+    let kind = if self.rng.random_bool(satellite_chance as f64) {
+      CarrierKind::Satellite
+    } else {
+      CarrierKind::Bomber
+    };
+    let moving_right = self.rng.random_bool(0.5);
+    let x = if moving_right { -CARRIER_SKY_EDGE } else { CARRIER_SKY_EDGE };
+    let y = self.rng.random_range(kind.altitude_range());
+    self.carriers.push(Carrier::new(kind, Vec2::new(x, y), moving_right));
+  }
+
+  // Move the carriers across the sky and drop missiles on the way
+  fn update_carriers(&mut self, dt: f32) {
+    let mut drops = Vec::new();
+    for carrier in &mut self.carriers {
+      if carrier.advance(dt) && carrier.pos.x.abs() < CARRIER_DROP_EDGE {
+        drops.push((carrier.pos, carrier.kind.dropped_missile()));
+      }
+    }
+    for (pos, enemy) in drops {
+      if let Some(missile) = self.aim_enemy_missile(pos, enemy) {
+        self.missiles.push(missile);
+      }
+    }
+
+    // Carriers that crossed the whole sky are gone
+    self.carriers.retain(|carrier| carrier.pos.x.abs() <= CARRIER_SKY_EDGE);
+  }
+
+  fn update_missiles(&mut self, dt: f32) {
+    let mut new_explosions = Vec::new();
+    let mut new_missiles = Vec::new();
+    let mut bunker_hits = Vec::new();
+
+    let mut forks = Vec::new();
+
+    // Update all missiles
+    for missile in &mut self.missiles {
+      if !missile.exploded {
+        // Evasive missiles pick a new heading whenever they reached their last waypoint
+        let to_target = missile.target_pos - missile.current_pos;
+        let is_evasive = missile.enemy_kind() == Some(EnemyKind::Evasive);
+        if is_evasive && missile.waypoint.is_none() && to_target.length() > EVASIVE_FINAL_APPROACH {
+          let ahead = to_target.normalize() * EVASIVE_LEG_LENGTH;
+          let swerve = ahead.perp().normalize() * self.rng.random_range(-EVASIVE_SWERVE..EVASIVE_SWERVE);
+          let waypoint = missile.current_pos + ahead + swerve;
+          missile.turn_towards(Vec2::new(waypoint.x.clamp(-390.0, 390.0), waypoint.y));
+        }
+
+        // Smart bombs dodge the explosions in their way
+        if missile.enemy_kind() == Some(EnemyKind::SmartBomb) {
+          missile.steer(smart_bomb_heading(missile.current_pos, missile.target_pos, &self.explosions));
+        }
+
+        let reached_target = missile.advance(dt);
+
+        match missile.kind {
+          // MIRVs fork into several missiles once they are low enough
+          MissileKind::Enemy {
+            enemy: EnemyKind::Mirv, ..
+          } if missile.current_pos.y >= MIRV_FORK_Y => {
+            missile.exploded = true;
+            forks.push(missile.current_pos);
+          }
+          // Enemy missile
+          MissileKind::Enemy { target_bunker_idx, .. } => {
+            // Check if missile hit its target bunker
+            if reached_target && target_bunker_idx < self.bunkers.len() {
+              missile.exploded = true;
+              bunker_hits.push(target_bunker_idx);
+              // The direct hit already damaged the bunker, so the explosion must not damage it again
+              let chain = self.chains.start(false);
+              let mut explosion = Explosion::new_default(self.bunkers[target_bunker_idx].pos).in_chain(chain).hostile();
+              explosion.mark_bunker_damaged(target_bunker_idx);
+              new_explosions.push(explosion);
+            }
+          }
+          // Player missile
+          MissileKind::Player => match missile.behaviour {
+            MissileBehaviour::Split { warheads } => {
+              // Release the warheads shortly before reaching the target
+              if missile.current_pos.distance(missile.target_pos) <= WARHEAD_SPLIT_DISTANCE {
+                missile.exploded = true;
+                let chain = self.chains.start(true);
+                new_missiles.extend(Self::split_missile(missile, warheads, chain));
+              }
+            }
+            MissileBehaviour::Direct => {
+              // Check if missile reached target
+              if reached_target {
+                missile.exploded = true;
+                // Use player's skills for player missiles
+                let params = ExplosionParams::from(&self.player).scaled(missile.explosion_scale);
+                let chain = missile.chain.unwrap_or_else(|| self.chains.start(true));
+                new_explosions.push(Explosion::new(missile.current_pos, params).in_chain(chain));
+              }
+            }
+          },
+        }
+      }
+    }
+
+    // Release the warheads of forked MIRVs, each at a different bunker as long as enough are left
+    for pos in forks {
+      for target_bunker_idx in self.choose_target_bunkers(MIRV_WARHEADS as usize) {
+        new_missiles.push(self.enemy_missile(pos, EnemyKind::Standard, target_bunker_idx));
+      }
+    }
+
+    // Add all new explosions and warheads
+    self.add_explosions(new_explosions);
+    self.missiles.append(&mut new_missiles);
+
+    for bunker_idx in bunker_hits {
+      self.damage_bunker(bunker_idx, ENEMY_MISSILE_DAMAGE);
+    }
+  }
+
+  // Damage a bunker, reduced by the armour of the player
+  fn damage_bunker(&mut self, bunker_idx: usize, damage: f32) {
+    let damage = damage * (1.0 - self.player.get_armour());
+    if self.bunkers[bunker_idx].apply_damage(damage) {
+      self.stats.bunkers_lost.push(BunkerLoss {
+        bunker_idx,
+        game_time: self.game_time,
+      });
+      let pos = self.bunkers[bunker_idx].pos;
+      self.events.push(GameEvent::BunkerDestroyed { bunker_idx, pos });
+    }
+  }
+
+  // Enemy explosions damage every active bunker in reach, but only once per explosion
+  fn apply_splash_damage(&mut self) {
+    let mut bunker_hits = Vec::new();
+    for explosion in self.explosions.iter_mut().filter(|explosion| explosion.hostile) {
+      for (bunker_idx, bunker) in self.bunkers.iter().enumerate() {
+        let in_reach = bunker.pos.distance(explosion.pos) <= explosion.radius + BUNKER_WIDTH / 2.0;
+        if bunker.active && in_reach && explosion.mark_bunker_damaged(bunker_idx) {
+          bunker_hits.push(bunker_idx);
+        }
+      }
+    }
+
+    for bunker_idx in bunker_hits {
+      self.damage_bunker(bunker_idx, EXPLOSION_SPLASH_DAMAGE);
+    }
+  }
+
+  // Create the warheads of a split missile. They fan out around the original target
+  // and all belong to the same chain, so they count as a single shot in the statistics.
+  fn split_missile(missile: &Missile, warheads: u32, chain: usize) -> Vec<Missile> {
+    (0..warheads)
+      .map(|i| {
+        let angle = std::f32::consts::TAU * i as f32 / warheads as f32;
+        let target_pos = missile.target_pos + Vec2::from_angle(angle) * WARHEAD_SPREAD;
+        Missile::new(missile.current_pos, target_pos, MissileKind::Player, missile.speed)
+          .with_explosion_scale(WARHEAD_EXPLOSION_SCALE)
+          .in_chain(chain)
+      })
+      .collect()
+  }
+
+  fn award_experience(&mut self, exp: f32) {
+    self.stats.add_experience(exp);
+    let new_stars = self.player.add_experience(exp);
+
+    for _ in 0..new_stars {
+      self.spawn_star();
+    }
+  }
+
+  // Spawn a star at a random position above the bunkers
+  fn spawn_star(&mut self) {
+    // Random position above the bunkers but below the top of the screen
+    let x = self.rng.random_range(-350.0..350.0);
+    let y = self.rng.random_range(-250.0..200.0); // Above bunkers, below top
+
+    self.stars.push(Star::new(Vec2::new(x, y)));
+  }
+
+  fn add_explosions(&mut self, explosions: Vec<Explosion>) {
+    for explosion in explosions {
+      self.events.push(GameEvent::Explosion {
+        pos: explosion.pos,
+        max_radius: explosion.max_radius,
+        hostile: explosion.hostile,
+      });
+      self.explosions.push(explosion);
+    }
+  }
+
+  fn update_explosions(&mut self, dt: f32) {
+    // Collect positions for new explosions
+    let mut new_explosions = Vec::new();
+    // Collect the experience for destroyed enemies
+    let mut earned_experience = Vec::new();
+
+    // Update existing explosions
+    for explosion in &mut self.explosions {
+      // Use the explosion's update method to handle growth mechanics
+      explosion.update(dt);
+    }
+
+    // Check for chain reactions with missiles and stars
+    for explosion in &self.explosions {
+      // Check all missiles
+      for missile in &mut self.missiles {
+        let in_reach = missile.current_pos.distance(explosion.pos) <= explosion.radius;
+        if !missile.exploded && in_reach && missile.take_hit(dt) {
+          missile.exploded = true;
+          // Check if it's a player missile or an enemy missile
+          if missile.is_player_missile() {
+            // Player missile - use player's skills
+            let params = ExplosionParams::from(&self.player).scaled(missile.explosion_scale);
+            new_explosions.push(Explosion::new(missile.current_pos, params).in_chain(explosion.chain));
+          } else {
+            // Enemy missile - use default parameters, its warhead can still hurt nearby bunkers
+            new_explosions.push(Explosion::new_default(missile.current_pos).in_chain(explosion.chain).hostile());
+            // Experience depends on the height and the kind of the missile
+            let multiplier = missile.enemy_kind().map_or(1.0, EnemyKind::experience_multiplier);
+            earned_experience.push(Self::calculate_experience_for_missile(missile.current_pos) * multiplier);
+            Self::record_kill(&mut self.stats, &mut self.chains[explosion.chain], missile.current_pos);
+            self.events.push(GameEvent::MissileIntercepted {
+              pos: missile.current_pos,
+              chain_kills: self.chains[explosion.chain].kills,
+            });
+          }
+        }
+      }
+
+      // Check all carriers, they go down in an explosion of their own
+      for carrier in &mut self.carriers {
+        if carrier.is_hit_by_explosion(explosion.pos, explosion.radius) {
+          carrier.destroyed = true;
+          new_explosions.push(Explosion::new_default(carrier.pos).in_chain(explosion.chain));
+          earned_experience.push(carrier.kind.bonus_experience());
+          self.stats.carriers_destroyed += 1;
+        }
+      }
+
+      // Check if any stars are hit by this explosion
+      self.stars.retain(|star| {
+        if star.is_hit_by_explosion(explosion.pos, explosion.radius) {
+          self.level_ups_left += 1;
+          self.events.push(GameEvent::StarCollected { pos: star.pos });
+          false
+        } else {
+          true
+        }
+      });
+    }
+
+    // Award experience for destroyed enemies
+    for exp in earned_experience {
+      self.award_experience(exp);
+    }
+
+    // Add all new explosions
+    self.add_explosions(new_explosions);
+
+    self.apply_splash_damage();
+
+    // Remove finished explosions
+    self.explosions.retain(|e| !e.has_ended());
+
+    // Remove exploded missiles and destroyed carriers
+    self.missiles.retain(|m| !m.exploded);
+    self.carriers.retain(|c| !c.destroyed);
+  }
+
+  // Update the statistics for an enemy missile destroyed at `pos` by an explosion of `chain`
+  fn record_kill(stats: &mut RunStats, chain: &mut Chain, pos: Vec2) {
+    stats.missiles_intercepted += 1;
+    stats.highest_kill_altitude = stats.highest_kill_altitude.max(GROUND_LEVEL - pos.y);
+
+    chain.kills += 1;
+    stats.longest_chain = stats.longest_chain.max(chain.kills);
+    if chain.fired_by_player && chain.kills == 1 {
+      stats.missiles_hit += 1;
+    }
+  }
+
+  fn check_game_over(&mut self) {
+    // Game is over if all bunkers are destroyed
+    if self.bunkers.iter().all(|b| !b.active) {
+      self.game_over = true;
+      self.events.push(GameEvent::GameOver);
+    }
+  }
+
+  // Offer two random skills if a level up is pending
+  fn show_skill_selection_menu(&mut self) {
+    if self.skill_options.is_empty() && self.level_ups_left > 0 {
+      self.skill_options = SkillBook::get().random_subset(&self.player, 2, &mut self.rng);
+
+      // Every skill is maxed out, so there is nothing left to choose
+      if self.skill_options.is_empty() {
+        self.level_ups_left = 0;
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn new_world() -> World {
+    World::new(42, GameMode::Endless, Difficulty::Normal)
+  }
+
+  // Step the world without commands until `done` returns true, for at most `seconds`
+  fn step_until(world: &mut World, seconds: f32, mut done: impl FnMut(&World) -> bool) -> bool {
+    let ticks = (seconds / SIMULATION_DT) as usize;
+    for _ in 0..ticks {
+      world.step(SIMULATION_DT, &[]);
+      if done(world) {
+        return true;
+      }
+    }
+    false
+  }
+
+  // A run of `seconds` where the player fires at a moving target twice a second and always picks the first skill
+  fn play(seed: u64, seconds: f32) -> World {
+    let mut world = World::new(seed, GameMode::Endless, Difficulty::Insane);
+    let ticks = (seconds / SIMULATION_DT) as u64;
+    for tick in 0..ticks {
+      let mut commands = Vec::new();
+      if world.is_skill_selection_active() {
+        commands.push(Command::PickSkill(0));
+      } else if tick % 60 == 0 {
+        let angle = tick as f32 * 0.01;
+        commands.push(Command::Fire(Vec2::new(angle.sin() * 300.0, -100.0 + angle.cos() * 100.0)));
+      }
+      world.step(SIMULATION_DT, &commands);
+    }
+    world
+  }
+
+  // Everything about the state of a world that can be compared
+  fn snapshot(world: &World) -> impl PartialEq + std::fmt::Debug + use<> {
+    (
+      (world.tick(), world.game_time(), world.is_game_over()),
+      world.stats().clone(),
+      (world.player().player_level(), world.player().experience()),
+      (world.missiles().iter())
+        .map(|missile| (missile.current_pos, missile.is_player_missile()))
+        .collect::<Vec<_>>(),
+      (world.bunkers().iter())
+        .map(|bunker| (bunker.active, bunker.ammo, bunker.health))
+        .collect::<Vec<_>>(),
+      (world.explosions().iter())
+        .map(|explosion| (explosion.pos, explosion.radius))
+        .collect::<Vec<_>>(),
+      world.stars().iter().map(|star| star.pos).collect::<Vec<_>>(),
+      world.carriers().iter().map(|carrier| carrier.pos).collect::<Vec<_>>(),
+    )
+  }
+
+  #[test]
+  fn same_seed_and_commands_play_out_identically() {
+    let world = play(7, 90.0);
+    // Make sure the run was eventful enough to mean something
+    assert!(world.stats().missiles_fired > 10);
+    assert!(world.stats().missiles_intercepted > 0);
+    assert_eq!(snapshot(&world), snapshot(&play(7, 90.0)));
+    assert_ne!(snapshot(&world), snapshot(&play(8, 90.0)));
+  }
+
+  // Let a MIRV fork right away and return the bunkers its warheads are aimed at
+  fn fork_mirv(world: &mut World) -> Vec<usize> {
+    let fork_pos = Vec2::new(0.0, MIRV_FORK_Y);
+    let kind = MissileKind::Enemy { enemy: EnemyKind::Mirv, target_bunker_idx: 0 };
+    world.missiles.push(Missile::new(fork_pos, world.bunkers[0].pos, kind, 1.0));
+    world.step(SIMULATION_DT, &[]);
+    (world.missiles().iter())
+      .filter(|missile| missile.start_pos.distance(fork_pos) < 1.0)
+      .filter_map(|missile| match missile.kind {
+        MissileKind::Enemy { enemy: EnemyKind::Standard, target_bunker_idx } => Some(target_bunker_idx),
+        _ => None,
+      })
+      .collect()
+  }
+
+  #[test]
+  fn mirv_warheads_spread_over_the_bunkers() {
+    for seed in 0..20 {
+      let mut world = World::new(seed, GameMode::Endless, Difficulty::Normal);
+      let mut targets = fork_mirv(&mut world);
+      targets.sort();
+      assert_eq!(targets, [0, 1, 2]);
+
+      // With fewer bunkers than warheads, every bunker that is left gets hit
+      let mut world = World::new(seed, GameMode::Endless, Difficulty::Normal);
+      world.damage_bunker(1, f32::MAX);
+      let mut targets = fork_mirv(&mut world);
+      targets.sort();
+      targets.dedup();
+      assert_eq!(targets, [0, 2]);
+    }
+  }
+
+  #[test]
+  fn finished_chains_free_their_slots() {
+    // Most of the fired missiles started a chain of their own
+    let world = play(7, 90.0);
+    assert!(world.stats().missiles_fired > 30);
+    assert!(world.chains.capacity() < 10, "{} chain slots", world.chains.capacity());
+  }
+
+  #[test]
+  fn fire_from_launches_a_missile_that_explodes_at_the_target() {
+    let mut world = new_world();
+    let target = Vec2::new(0.0, 0.0);
+    let ammo = world.bunkers()[1].ammo;
+
+    world.step(SIMULATION_DT, &[Command::FireFrom(1, target)]);
+    assert!(world.events().iter().any(|event| matches!(event, GameEvent::MissileLaunched { .. })));
+    assert_eq!(world.bunkers()[1].ammo, ammo - 1);
+    assert_eq!(world.missiles().iter().filter(|missile| missile.is_player_missile()).count(), 1);
+    assert_eq!(world.stats().missiles_fired, 1);
+
+    let exploded = step_until(&mut world, 10.0, |world| {
+      (world.explosions().iter()).any(|explosion| !explosion.hostile && explosion.pos.distance(target) < 1.0)
+    });
+    assert!(exploded);
+    assert!(!world.missiles().iter().any(|missile| missile.is_player_missile()));
+  }
+
+  #[test]
+  fn fire_needs_a_loaded_round() {
+    let mut world = new_world();
+    world.bunkers[1].ammo = 0;
+
+    world.step(SIMULATION_DT, &[Command::FireFrom(1, Vec2::ZERO)]);
+    assert!(world.missiles().iter().all(|missile| !missile.is_player_missile()));
+    assert_eq!(world.stats().missiles_fired, 0);
+  }
+
+  #[test]
+  fn game_ends_once_all_bunkers_are_destroyed() {
+    let mut world = new_world();
+    world.damage_bunker(0, f32::MAX);
+    world.damage_bunker(1, f32::MAX);
+    world.step(SIMULATION_DT, &[]);
+    assert!(!world.is_game_over());
+
+    world.damage_bunker(2, f32::MAX);
+    world.step(SIMULATION_DT, &[]);
+    assert!(world.is_game_over());
+    assert!(world.events().iter().any(|event| matches!(event, GameEvent::GameOver)));
+    assert_eq!(world.stats().bunkers_lost.len(), 3);
+
+    // Nothing happens anymore after the game is over
+    let (tick, game_time) = (world.tick(), world.game_time());
+    world.step(SIMULATION_DT, &[Command::Fire(Vec2::ZERO)]);
+    assert_eq!(world.tick(), tick);
+    assert_eq!(world.game_time(), game_time);
+    assert!(world.events().is_empty());
+  }
+
+  #[test]
+  fn skill_choice_pauses_the_simulation_until_a_skill_is_picked() {
+    let mut world = new_world();
+    world.level_ups_left = 1;
+
+    for _ in 0..10 {
+      world.step(SIMULATION_DT, &[Command::Fire(Vec2::ZERO)]);
+    }
+    assert!(world.is_skill_selection_active());
+    assert!(!world.skill_options().is_empty());
+    assert_eq!(world.game_time(), 0.0);
+    assert!(world.missiles().is_empty());
+
+    let skill = world.skill_options()[0].clone();
+    world.step(SIMULATION_DT, &[Command::PickSkill(0)]);
+    assert!(!world.is_skill_selection_active());
+    assert!(world.events().iter().any(|event| matches!(event, GameEvent::LevelUp)));
+    assert_eq!(world.player().get_skill_level(&skill), 1);
+    assert!(world.game_time() > 0.0);
+  }
+}
@@ -0,0 +1,219 @@
+use serde::{Deserialize, Serialize};
+
+use crate::game::difficulty::Difficulty;
+use crate::game::waves::GameMode;
+use crate::game::world::World;
+use crate::utils::storage;
+
+const STORAGE_KEY: &str = "highscores.ron";
+/// Number of runs kept in the table of every mode and difficulty
+pub const MAX_HIGH_SCORES: usize = 10;
+
+/// A finished run in the high score table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HighScore {
+  pub game_time: f32,
+  pub player_level: u32,
+  pub missiles_intercepted: u32,
+  pub seed: u64,
+  /// When the run ended, in seconds since the unix epoch
+  pub timestamp: u64,
+  pub mode: GameMode,
+  pub difficulty: Difficulty,
+  /// Wave the run ended in, 0 in endless mode
+  pub wave: u32,
+}
+
+impl HighScore {
+  pub fn from_world(world: &World) -> Self {
+    Self {
+      game_time: world.game_time(),
+      player_level: world.player().player_level(),
+      missiles_intercepted: world.stats().missiles_intercepted,
+      seed: world.seed(),
+      timestamp: storage::unix_time(),
+      mode: world.mode(),
+      difficulty: world.difficulty(),
+      wave: world.wave().map_or(0, |wave| wave.number),
+    }
+  }
+
+  /// Whether the run is listed in the table of the given mode and difficulty
+  pub fn is_in(&self, mode: GameMode, difficulty: Difficulty) -> bool {
+    self.mode == mode && self.difficulty == difficulty
+  }
+
+  // Runs in wave mode are ranked by the wave they reached first
+  fn beats(&self, other: &HighScore) -> bool {
+    match self.mode {
+      GameMode::Endless => self.game_time > other.game_time,
+      GameMode::Waves => (self.wave, self.game_time) > (other.wave, other.game_time),
+    }
+  }
+}
+
+/// The best runs on this machine for every mode and difficulty, ordered from best to worst
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HighScores {
+  entries: Vec<HighScore>,
+}
+
+impl HighScores {
+  /// Load the stored table. A missing or unreadable table is treated as empty.
+  pub fn load() -> Self {
+    let Some(text) = storage::load(STORAGE_KEY) else {
+      return Self::default();
+    };
+    ron::from_str(&text).unwrap_or_else(|err| {
+      log::error!("Ignoring broken high score table: {err}");
+      Self::default()
+    })
+  }
+
+  pub fn save(&self) {
+    let result = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+      .map_err(|err| err.to_string())
+      .and_then(|text| storage::save(STORAGE_KEY, &text));
+    if let Err(err) = result {
+      log::error!("Could not save high scores: {err}");
+    }
+  }
+
+  /// The runs of the given mode and difficulty, from best to worst
+  pub fn entries(&self, mode: GameMode, difficulty: Difficulty) -> Vec<&HighScore> {
+    self.entries.iter().filter(|entry| entry.is_in(mode, difficulty)).collect()
+  }
+
+  /// Add a run to the table of its mode and difficulty.
+  /// Returns its rank, or `None` if it didn't make it into the table.
+  pub fn insert(&mut self, score: HighScore) -> Option<usize> {
+    let (mode, difficulty) = (score.mode, score.difficulty);
+    let entries = self.entries(mode, difficulty);
+    let rank = entries.iter().position(|entry| score.beats(entry)).unwrap_or(entries.len());
+    if rank >= MAX_HIGH_SCORES {
+      return None;
+    }
+
+    // Insert in front of the entry that is now ranked below this run, or at the end
+    let index = (self.entries.iter().enumerate())
+      .filter(|(_, entry)| entry.is_in(mode, difficulty))
+      .nth(rank)
+      .map_or(self.entries.len(), |(index, _)| index);
+    self.entries.insert(index, score);
+
+    // Drop the runs of this table that fell out of it
+    let mut kept = 0;
+    self.entries.retain(|entry| {
+      if !entry.is_in(mode, difficulty) {
+        return true;
+      }
+      kept += 1;
+      kept <= MAX_HIGH_SCORES
+    });
+    Some(rank)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn run(mode: GameMode, difficulty: Difficulty, game_time: f32, wave: u32) -> HighScore {
+    HighScore {
+      game_time,
+      player_level: 1,
+      missiles_intercepted: 0,
+      seed: 0,
+      timestamp: 0,
+      mode,
+      difficulty,
+      wave,
+    }
+  }
+
+  fn endless(game_time: f32) -> HighScore {
+    run(GameMode::Endless, Difficulty::Normal, game_time, 0)
+  }
+
+  fn times(high_scores: &HighScores) -> Vec<f32> {
+    (high_scores.entries(GameMode::Endless, Difficulty::Normal).iter())
+      .map(|entry| entry.game_time)
+      .collect()
+  }
+
+  fn full_table() -> HighScores {
+    let mut high_scores = HighScores::default();
+    for game_time in 1..=MAX_HIGH_SCORES {
+      high_scores.insert(endless(game_time as f32 * 10.0));
+    }
+    high_scores
+  }
+
+  #[test]
+  fn runs_are_ranked_from_best_to_worst() {
+    let mut high_scores = HighScores::default();
+    assert_eq!(high_scores.insert(endless(30.0)), Some(0));
+    assert_eq!(high_scores.insert(endless(10.0)), Some(1));
+    assert_eq!(high_scores.insert(endless(20.0)), Some(1));
+    assert_eq!(high_scores.insert(endless(40.0)), Some(0));
+    assert_eq!(times(&high_scores), [40.0, 30.0, 20.0, 10.0]);
+  }
+
+  #[test]
+  fn ties_rank_below_the_earlier_run() {
+    let mut high_scores = HighScores::default();
+    let mut first = endless(20.0);
+    first.seed = 1;
+    high_scores.insert(first);
+    high_scores.insert(endless(30.0));
+    assert_eq!(high_scores.insert(endless(20.0)), Some(2));
+    let entries = high_scores.entries(GameMode::Endless, Difficulty::Normal);
+    assert_eq!(entries[1].seed, 1);
+    assert_eq!(entries[2].seed, 0);
+  }
+
+  #[test]
+  fn full_tables_drop_their_worst_run() {
+    let mut high_scores = full_table();
+    assert_eq!(high_scores.insert(endless(55.0)), Some(5));
+    let times = times(&high_scores);
+    assert_eq!(times.len(), MAX_HIGH_SCORES);
+    assert_eq!(times[5], 55.0);
+    assert_eq!(times.last(), Some(&20.0));
+  }
+
+  #[test]
+  fn runs_that_dont_qualify_are_rejected() {
+    let mut high_scores = full_table();
+    let before = times(&high_scores);
+    assert_eq!(high_scores.insert(endless(5.0)), None);
+    // Tying the last run doesn't push it out either
+    assert_eq!(high_scores.insert(endless(10.0)), None);
+    assert_eq!(times(&high_scores), before);
+  }
+
+  #[test]
+  fn every_mode_and_difficulty_has_its_own_table() {
+    let mut high_scores = HighScores::default();
+    high_scores.insert(run(GameMode::Waves, Difficulty::Normal, 5.0, 3));
+    high_scores.insert(run(GameMode::Endless, Difficulty::Hard, 1.0, 0));
+    let mut full = full_table();
+    full.entries.append(&mut high_scores.entries);
+    let mut high_scores = full;
+
+    // Filling up and trimming one table leaves the others alone
+    assert_eq!(high_scores.insert(endless(1000.0)), Some(0));
+    assert_eq!(high_scores.insert(run(GameMode::Endless, Difficulty::Hard, 0.5, 0)), Some(1));
+    assert_eq!(times(&high_scores).len(), MAX_HIGH_SCORES);
+    assert_eq!(high_scores.entries(GameMode::Waves, Difficulty::Normal).len(), 1);
+    assert_eq!(high_scores.entries(GameMode::Endless, Difficulty::Hard).len(), 2);
+  }
+
+  #[test]
+  fn wave_runs_are_ranked_by_wave_first() {
+    let mut high_scores = HighScores::default();
+    high_scores.insert(run(GameMode::Waves, Difficulty::Normal, 300.0, 4));
+    assert_eq!(high_scores.insert(run(GameMode::Waves, Difficulty::Normal, 200.0, 5)), Some(0));
+    assert_eq!(high_scores.insert(run(GameMode::Waves, Difficulty::Normal, 250.0, 4)), Some(2));
+  }
+}
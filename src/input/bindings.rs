@@ -0,0 +1,226 @@
+use std::collections::BTreeMap;
+
+use macroquad::input::{KeyCode, MouseButton};
+use serde::{Deserialize, Serialize};
+
+use super::gamepad::PadButton;
+
+/// Something the player can do, independent of the key or button that does it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Action {
+  /// Fire at the cursor from the nearest (or the selected) bunker
+  Fire,
+  FireLeft,
+  FireMiddle,
+  FireRight,
+  /// Select the next bunker for the "Bunker Keys" control scheme
+  NextBunker,
+  Rebuild,
+  Pause,
+  CursorUp,
+  CursorDown,
+  CursorLeft,
+  CursorRight,
+  MenuUp,
+  MenuDown,
+  MenuLeft,
+  MenuRight,
+  Confirm,
+  Back,
+}
+
+impl Action {
+  pub const ALL: [Action; 17] = [
+    Action::Fire,
+    Action::FireLeft,
+    Action::FireMiddle,
+    Action::FireRight,
+    Action::NextBunker,
+    Action::Rebuild,
+    Action::Pause,
+    Action::CursorUp,
+    Action::CursorDown,
+    Action::CursorLeft,
+    Action::CursorRight,
+    Action::MenuUp,
+    Action::MenuDown,
+    Action::MenuLeft,
+    Action::MenuRight,
+    Action::Confirm,
+    Action::Back,
+  ];
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Action::Fire => "Fire",
+      Action::FireLeft => "Fire Left Bunker",
+      Action::FireMiddle => "Fire Middle Bunker",
+      Action::FireRight => "Fire Right Bunker",
+      Action::NextBunker => "Next Bunker",
+      Action::Rebuild => "Rebuild Bunker",
+      Action::Pause => "Pause",
+      Action::CursorUp => "Cursor Up",
+      Action::CursorDown => "Cursor Down",
+      Action::CursorLeft => "Cursor Left",
+      Action::CursorRight => "Cursor Right",
+      Action::MenuUp => "Menu Up",
+      Action::MenuDown => "Menu Down",
+      Action::MenuLeft => "Menu Left",
+      Action::MenuRight => "Menu Right",
+      Action::Confirm => "Confirm",
+      Action::Back => "Back",
+    }
+  }
+
+  /// The actions that fire from a specific bunker, from left to right
+  pub fn fire_bunker(bunker_idx: usize) -> Option<Action> {
+    [Action::FireLeft, Action::FireMiddle, Action::FireRight].get(bunker_idx).copied()
+  }
+}
+
+/// A key, mouse button or gamepad button that triggers an action
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Binding {
+  Key(#[serde(with = "key_name")] KeyCode),
+  Mouse(#[serde(with = "mouse_button_name")] MouseButton),
+  Pad(PadButton),
+}
+
+impl Binding {
+  pub fn name(self) -> String {
+    match self {
+      // Digits are called Key0 to Key9
+      Binding::Key(key) => format!("{key:?}").trim_start_matches("Key").to_string(),
+      Binding::Mouse(MouseButton::Left) => "Left Click".to_string(),
+      Binding::Mouse(MouseButton::Right) => "Right Click".to_string(),
+      Binding::Mouse(MouseButton::Middle) => "Middle Click".to_string(),
+      Binding::Mouse(MouseButton::Unknown) => "Mouse".to_string(),
+      Binding::Pad(button) => format!("Pad {}", button.name()),
+    }
+  }
+
+  fn is_gamepad(self) -> bool {
+    matches!(self, Binding::Pad(_))
+  }
+}
+
+/// The bindings of all actions. Every action can have several bindings.
+/// Actions missing in the stored settings get their default bindings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "BTreeMap<Action, Vec<Binding>>", into = "BTreeMap<Action, Vec<Binding>>")]
+pub struct Bindings(BTreeMap<Action, Vec<Binding>>);
+
+impl Default for Bindings {
+  fn default() -> Self {
+    use Binding::{Key, Mouse, Pad};
+
+    let bindings = Action::ALL.map(|action| {
+      let bindings = match action {
+        Action::Fire => vec![Mouse(MouseButton::Left), Pad(PadButton::South)],
+        Action::FireLeft => vec![Key(KeyCode::A), Key(KeyCode::Key1), Pad(PadButton::LeftTrigger)],
+        Action::FireMiddle => vec![Key(KeyCode::S), Key(KeyCode::Key2), Pad(PadButton::North)],
+        Action::FireRight => vec![Key(KeyCode::D), Key(KeyCode::Key3), Pad(PadButton::RightTrigger)],
+        Action::NextBunker => vec![Mouse(MouseButton::Right), Pad(PadButton::RightBumper)],
+        Action::Rebuild => vec![Key(KeyCode::R), Pad(PadButton::West)],
+        Action::Pause => vec![Key(KeyCode::Escape), Key(KeyCode::P), Pad(PadButton::Start)],
+        Action::CursorUp => vec![Key(KeyCode::Up)],
+        Action::CursorDown => vec![Key(KeyCode::Down)],
+        Action::CursorLeft => vec![Key(KeyCode::Left)],
+        Action::CursorRight => vec![Key(KeyCode::Right)],
+        // The arrow keys also move the cursor, which doesn't select anything while hovering
+        Action::MenuUp => vec![Key(KeyCode::Up), Pad(PadButton::DPadUp)],
+        Action::MenuDown => vec![Key(KeyCode::Down), Pad(PadButton::DPadDown)],
+        Action::MenuLeft => vec![Key(KeyCode::Left), Pad(PadButton::DPadLeft)],
+        Action::MenuRight => vec![Key(KeyCode::Right), Pad(PadButton::DPadRight)],
+        Action::Confirm => vec![Key(KeyCode::Enter), Key(KeyCode::Space), Pad(PadButton::South)],
+        Action::Back => vec![Key(KeyCode::Escape), Pad(PadButton::East)],
+      };
+      (action, bindings)
+    });
+    Self(BTreeMap::from(bindings))
+  }
+}
+
+impl From<BTreeMap<Action, Vec<Binding>>> for Bindings {
+  fn from(stored: BTreeMap<Action, Vec<Binding>>) -> Self {
+    let mut bindings = Self::default();
+    bindings.0.extend(stored);
+    bindings
+  }
+}
+
+impl From<Bindings> for BTreeMap<Action, Vec<Binding>> {
+  fn from(bindings: Bindings) -> Self {
+    bindings.0
+  }
+}
+
+impl Bindings {
+  pub fn of(&self, action: Action) -> &[Binding] {
+    self.0.get(&action).map_or(&[], Vec::as_slice)
+  }
+
+  /// Bind `binding` to `action`. It replaces the other bindings of the action on the same device,
+  /// so keyboard and mouse bindings are kept when a gamepad button is bound and the other way around.
+  pub fn rebind(&mut self, action: Action, binding: Binding) {
+    let bindings = self.0.entry(action).or_default();
+    bindings.retain(|other| other.is_gamepad() != binding.is_gamepad());
+    bindings.push(binding);
+  }
+}
+
+// Every key that can be bound, so the names in the settings file can be turned back into keys
+const KEY_CODES: [KeyCode; 121] = [
+  KeyCode::Space, KeyCode::Apostrophe, KeyCode::Comma, KeyCode::Minus, KeyCode::Period, KeyCode::Slash, KeyCode::Key0,
+  KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4, KeyCode::Key5, KeyCode::Key6, KeyCode::Key7,
+  KeyCode::Key8, KeyCode::Key9, KeyCode::Semicolon, KeyCode::Equal, KeyCode::A, KeyCode::B, KeyCode::C, KeyCode::D,
+  KeyCode::E, KeyCode::F, KeyCode::G, KeyCode::H, KeyCode::I, KeyCode::J, KeyCode::K, KeyCode::L, KeyCode::M,
+  KeyCode::N, KeyCode::O, KeyCode::P, KeyCode::Q, KeyCode::R, KeyCode::S, KeyCode::T, KeyCode::U, KeyCode::V,
+  KeyCode::W, KeyCode::X, KeyCode::Y, KeyCode::Z, KeyCode::LeftBracket, KeyCode::Backslash, KeyCode::RightBracket,
+  KeyCode::GraveAccent, KeyCode::World1, KeyCode::World2, KeyCode::Escape, KeyCode::Enter, KeyCode::Tab,
+  KeyCode::Backspace, KeyCode::Insert, KeyCode::Delete, KeyCode::Right, KeyCode::Left, KeyCode::Down, KeyCode::Up,
+  KeyCode::PageUp, KeyCode::PageDown, KeyCode::Home, KeyCode::End, KeyCode::CapsLock, KeyCode::ScrollLock,
+  KeyCode::NumLock, KeyCode::PrintScreen, KeyCode::Pause, KeyCode::F1, KeyCode::F2, KeyCode::F3, KeyCode::F4,
+  KeyCode::F5, KeyCode::F6, KeyCode::F7, KeyCode::F8, KeyCode::F9, KeyCode::F10, KeyCode::F11, KeyCode::F12,
+  KeyCode::F13, KeyCode::F14, KeyCode::F15, KeyCode::F16, KeyCode::F17, KeyCode::F18, KeyCode::F19, KeyCode::F20,
+  KeyCode::F21, KeyCode::F22, KeyCode::F23, KeyCode::F24, KeyCode::F25, KeyCode::Kp0, KeyCode::Kp1, KeyCode::Kp2,
+  KeyCode::Kp3, KeyCode::Kp4, KeyCode::Kp5, KeyCode::Kp6, KeyCode::Kp7, KeyCode::Kp8, KeyCode::Kp9,
+  KeyCode::KpDecimal, KeyCode::KpDivide, KeyCode::KpMultiply, KeyCode::KpSubtract, KeyCode::KpAdd, KeyCode::KpEnter,
+  KeyCode::KpEqual, KeyCode::LeftShift, KeyCode::LeftControl, KeyCode::LeftAlt, KeyCode::LeftSuper,
+  KeyCode::RightShift, KeyCode::RightControl, KeyCode::RightAlt, KeyCode::RightSuper, KeyCode::Menu, KeyCode::Back,
+];
+
+// Keys are stored with the name of their `KeyCode` variant
+mod key_name {
+  use macroquad::input::KeyCode;
+  use serde::de::Error;
+  use serde::{Deserialize, Deserializer, Serializer};
+
+  pub fn serialize<S: Serializer>(key: &KeyCode, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("{key:?}"))
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<KeyCode, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    (super::KEY_CODES.into_iter())
+      .find(|key| format!("{key:?}") == name)
+      .ok_or_else(|| D::Error::custom(format!("Unknown key '{name}'")))
+  }
+}
+
+mod mouse_button_name {
+  use macroquad::input::MouseButton;
+  use serde::de::Error;
+  use serde::{Deserialize, Deserializer, Serializer};
+
+  pub fn serialize<S: Serializer>(button: &MouseButton, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("{button:?}"))
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<MouseButton, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    ([MouseButton::Left, MouseButton::Right, MouseButton::Middle].into_iter())
+      .find(|button| format!("{button:?}") == name)
+      .ok_or_else(|| D::Error::custom(format!("Unknown mouse button '{name}'")))
+  }
+}
@@ -0,0 +1,173 @@
+use macroquad::prelude::Vec2;
+use serde::{Deserialize, Serialize};
+
+// Stick deflections below this are treated as no deflection at all
+const STICK_DEADZONE: f32 = 0.2;
+
+/// Buttons of a gamepad, named after their position on the pad
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PadButton {
+  South,
+  East,
+  North,
+  West,
+  LeftBumper,
+  RightBumper,
+  LeftTrigger,
+  RightTrigger,
+  Select,
+  Start,
+  LeftStick,
+  RightStick,
+  DPadUp,
+  DPadDown,
+  DPadLeft,
+  DPadRight,
+}
+
+impl PadButton {
+  pub const ALL: [PadButton; 16] = [
+    PadButton::South,
+    PadButton::East,
+    PadButton::North,
+    PadButton::West,
+    PadButton::LeftBumper,
+    PadButton::RightBumper,
+    PadButton::LeftTrigger,
+    PadButton::RightTrigger,
+    PadButton::Select,
+    PadButton::Start,
+    PadButton::LeftStick,
+    PadButton::RightStick,
+    PadButton::DPadUp,
+    PadButton::DPadDown,
+    PadButton::DPadLeft,
+    PadButton::DPadRight,
+  ];
+
+  /// Name of the button on an Xbox style gamepad
+  pub fn name(self) -> &'static str {
+    match self {
+      PadButton::South => "A",
+      PadButton::East => "B",
+      PadButton::North => "Y",
+      PadButton::West => "X",
+      PadButton::LeftBumper => "LB",
+      PadButton::RightBumper => "RB",
+      PadButton::LeftTrigger => "LT",
+      PadButton::RightTrigger => "RT",
+      PadButton::Select => "Back",
+      PadButton::Start => "Start",
+      PadButton::LeftStick => "L3",
+      PadButton::RightStick => "R3",
+      PadButton::DPadUp => "Up",
+      PadButton::DPadDown => "Down",
+      PadButton::DPadLeft => "Left",
+      PadButton::DPadRight => "Right",
+    }
+  }
+}
+
+/// Buttons and left sticks of all connected gamepads combined
+#[derive(Debug, Clone, Default)]
+pub struct GamepadState {
+  /// Buttons that were pressed since the last poll
+  pub pressed: Vec<PadButton>,
+  /// Buttons that are held down
+  pub down: Vec<PadButton>,
+  /// Deflection of the left stick with a length of up to 1.0, y pointing down like on the screen
+  pub stick: Vec2,
+}
+
+impl GamepadState {
+  fn add_stick(&mut self, stick: Vec2) {
+    let length = stick.length();
+    if length > STICK_DEADZONE {
+      // Start from zero at the edge of the deadzone, so small deflections allow fine movement
+      let scaled = (length - STICK_DEADZONE) / (1.0 - STICK_DEADZONE);
+      self.stick = (self.stick + stick / length * scaled).clamp_length_max(1.0);
+    }
+  }
+}
+
+/// Read the state of the gamepads. Without the `gamepad` feature there are none.
+pub fn poll() -> GamepadState {
+  #[cfg(feature = "gamepad")]
+  return gilrs_backend::poll();
+  #[cfg(not(feature = "gamepad"))]
+  GamepadState::default()
+}
+
+#[cfg(feature = "gamepad")]
+mod gilrs_backend {
+  use std::cell::RefCell;
+
+  use gilrs::{Axis, Button, Event, EventType, Gilrs};
+  use macroquad::prelude::Vec2;
+
+  use super::{GamepadState, PadButton};
+
+  thread_local! {
+    // Gilrs can't be sent to other threads, but the game only reads input on the main thread anyway
+    static GILRS: RefCell<Option<Gilrs>> = RefCell::new(match Gilrs::new() {
+      Ok(gilrs) => Some(gilrs),
+      Err(err) => {
+        log::error!("Gamepads are not available: {err}");
+        None
+      }
+    });
+  }
+
+  fn to_gilrs(button: PadButton) -> Button {
+    match button {
+      PadButton::South => Button::South,
+      PadButton::East => Button::East,
+      PadButton::North => Button::North,
+      PadButton::West => Button::West,
+      PadButton::LeftBumper => Button::LeftTrigger,
+      PadButton::RightBumper => Button::RightTrigger,
+      PadButton::LeftTrigger => Button::LeftTrigger2,
+      PadButton::RightTrigger => Button::RightTrigger2,
+      PadButton::Select => Button::Select,
+      PadButton::Start => Button::Start,
+      PadButton::LeftStick => Button::LeftThumb,
+      PadButton::RightStick => Button::RightThumb,
+      PadButton::DPadUp => Button::DPadUp,
+      PadButton::DPadDown => Button::DPadDown,
+      PadButton::DPadLeft => Button::DPadLeft,
+      PadButton::DPadRight => Button::DPadRight,
+    }
+  }
+
+  fn from_gilrs(button: Button) -> Option<PadButton> {
+    PadButton::ALL.into_iter().find(|&pad_button| to_gilrs(pad_button) == button)
+  }
+
+  pub fn poll() -> GamepadState {
+    GILRS.with_borrow_mut(|gilrs| {
+      let mut state = GamepadState::default();
+      let Some(gilrs) = gilrs else {
+        return state;
+      };
+
+      while let Some(Event { event, .. }) = gilrs.next_event() {
+        if let EventType::ButtonPressed(button, _) = event
+          && let Some(button) = from_gilrs(button)
+        {
+          state.pressed.push(button);
+        }
+      }
+
+      for (_, gamepad) in gilrs.gamepads() {
+        for button in PadButton::ALL {
+          if gamepad.is_pressed(to_gilrs(button)) && !state.down.contains(&button) {
+            state.down.push(button);
+          }
+        }
+        // Up is positive on the stick, but negative on the screen
+        state.add_stick(Vec2::new(gamepad.value(Axis::LeftStickX), -gamepad.value(Axis::LeftStickY)));
+      }
+      state
+    })
+  }
+}
@@ -0,0 +1,148 @@
+//! Input actions the player can rebind, and a cursor that follows the mouse but can also be moved
+//! with the keyboard or a gamepad stick. Without the `gamepad` feature there are no gamepads.
+
+use std::sync::{Mutex, MutexGuard};
+
+use lazy_static::lazy_static;
+use macroquad::input::{
+  MouseButton, get_last_key_pressed, is_key_down, is_key_pressed, is_mouse_button_down, is_mouse_button_pressed,
+};
+use macroquad::prelude::Vec2;
+
+use crate::game::frame::Frame;
+use crate::settings::Settings;
+use crate::utils::mouse_pos;
+
+mod bindings;
+mod gamepad;
+
+pub use bindings::{Action, Binding, Bindings};
+pub use gamepad::PadButton;
+use gamepad::GamepadState;
+
+lazy_static! {
+  static ref INPUT: Mutex<Input> = Mutex::new(Input::new());
+}
+
+// Speed of the cursor in screen heights per second when it starts to move and after it accelerated fully
+const CURSOR_START_SPEED: f32 = 0.25;
+const CURSOR_MAX_SPEED: f32 = 1.2;
+// Seconds until the cursor reaches its full speed
+const CURSOR_ACCELERATION_TIME: f32 = 0.5;
+
+pub struct Input {
+  bindings: Bindings,
+  gamepad: GamepadState,
+  // Position of the cursor in screen coordinates
+  cursor: Vec2,
+  // Whether the mouse or the stick moved the cursor this frame
+  cursor_moved: bool,
+  // Seconds the cursor has been moved without the mouse
+  cursor_move_time: f32,
+  last_mouse_pos: Option<Vec2>,
+}
+
+impl Input {
+  fn new() -> Self {
+    Self {
+      bindings: Bindings::default(),
+      gamepad: GamepadState::default(),
+      cursor: Vec2::ZERO,
+      cursor_moved: false,
+      cursor_move_time: 0.0,
+      last_mouse_pos: None,
+    }
+  }
+
+  pub fn get() -> MutexGuard<'static, Self> {
+    INPUT.lock().unwrap()
+  }
+
+  /// Read the gamepads and move the cursor. Called once at the start of every frame.
+  pub fn update() {
+    let gamepad = gamepad::poll();
+    let bindings = Settings::get().bindings.clone();
+    let frame = Frame::get();
+
+    let mut input = Self::get();
+    input.gamepad = gamepad;
+    input.bindings = bindings;
+    input.move_cursor(frame.t, frame.screen_size);
+  }
+
+  fn move_cursor(&mut self, dt: f32, screen_size: Vec2) {
+    self.cursor_moved = false;
+
+    // The mouse always wins, so the cursor jumps back to it as soon as it moves
+    let mouse = mouse_pos();
+    if self.last_mouse_pos != Some(mouse) {
+      self.last_mouse_pos = Some(mouse);
+      self.cursor = mouse;
+      self.cursor_moved = true;
+      self.cursor_move_time = 0.0;
+      return;
+    }
+
+    let directions = [
+      (Action::CursorUp, Vec2::NEG_Y),
+      (Action::CursorDown, Vec2::Y),
+      (Action::CursorLeft, Vec2::NEG_X),
+      (Action::CursorRight, Vec2::X),
+    ];
+    let keys: Vec2 = (directions.into_iter())
+      .filter(|&(action, _)| self.is_down(action))
+      .map(|(_, direction)| direction)
+      .sum();
+    let direction = (keys.normalize_or_zero() + self.gamepad.stick).clamp_length_max(1.0);
+    if direction == Vec2::ZERO {
+      self.cursor_move_time = 0.0;
+      return;
+    }
+
+    // Start slow for precise aiming and speed up the longer the cursor moves
+    self.cursor_move_time += dt;
+    let acceleration = (self.cursor_move_time / CURSOR_ACCELERATION_TIME).min(1.0);
+    let speed = CURSOR_START_SPEED + (CURSOR_MAX_SPEED - CURSOR_START_SPEED) * acceleration;
+    self.cursor = (self.cursor + direction * speed * screen_size.y * dt).clamp(Vec2::ZERO, screen_size);
+    self.cursor_moved = self.gamepad.stick != Vec2::ZERO;
+  }
+
+  /// Position of the cursor in screen coordinates
+  pub fn cursor(&self) -> Vec2 {
+    self.cursor
+  }
+
+  /// Whether the mouse or a stick moved the cursor this frame. Moving it with the cursor keys doesn't count,
+  /// because they usually also navigate menus and hovering must not undo the selection made with them.
+  pub fn cursor_moved(&self) -> bool {
+    self.cursor_moved
+  }
+
+  /// Whether one of the bindings of `action` was pressed this frame
+  pub fn is_pressed(&self, action: Action) -> bool {
+    self.bindings.of(action).iter().any(|&binding| match binding {
+      Binding::Key(key) => is_key_pressed(key),
+      Binding::Mouse(button) => is_mouse_button_pressed(button),
+      Binding::Pad(button) => self.gamepad.pressed.contains(&button),
+    })
+  }
+
+  /// Whether one of the bindings of `action` is held down
+  pub fn is_down(&self, action: Action) -> bool {
+    self.bindings.of(action).iter().any(|&binding| match binding {
+      Binding::Key(key) => is_key_down(key),
+      Binding::Mouse(button) => is_mouse_button_down(button),
+      Binding::Pad(button) => self.gamepad.down.contains(&button),
+    })
+  }
+
+  /// Any key or button that was pressed this frame, used to bind it to an action
+  pub fn pressed_binding(&self) -> Option<Binding> {
+    let mouse_button = [MouseButton::Left, MouseButton::Right, MouseButton::Middle]
+      .into_iter()
+      .find(|&button| is_mouse_button_pressed(button));
+    (get_last_key_pressed().map(Binding::Key))
+      .or(mouse_button.map(Binding::Mouse))
+      .or(self.gamepad.pressed.first().map(|&button| Binding::Pad(button)))
+  }
+}
@@ -3,38 +3,111 @@
 use std::{mem::transmute, sync::Mutex};
 
 use env_logger::Env;
+use game::frame::Frame;
+use input::Input;
 use macroquad::prelude::*;
+use settings::Settings;
+use utils::diagnostics::Diagnostics;
 
+mod audio;
 mod game;
+mod highscores;
+mod input;
+mod screens;
+mod settings;
 mod utils;
 
 trait AppState {
+  /// Handle input and advance the state. Only called for the topmost state.
   fn update(&mut self);
+  /// Draw the state. Called for all states from the bottom to the top,
+  /// so overlays like the pause menu are drawn over the state they cover.
+  fn render(&self);
 }
 
+enum Transition {
+  Set(Box<dyn AppState + Send>),
+  Push(Box<dyn AppState + Send>),
+  Pop,
+}
+
+/// Replace all states with the given state
 fn set_state(state: Box<dyn AppState>) {
+  request_transition(Transition::Set(unsafe { transmute::<Box<dyn AppState>, Box<dyn AppState + Send>>(state) }));
+}
+
+/// Put a state on top of the current one, which keeps being rendered but no longer updated
+fn push_state(state: Box<dyn AppState>) {
+  request_transition(Transition::Push(unsafe { transmute::<Box<dyn AppState>, Box<dyn AppState + Send>>(state) }));
+}
+
+/// Remove the topmost state and continue with the one below
+fn pop_state() {
+  request_transition(Transition::Pop);
+}
+
+fn request_transition(transition: Transition) {
   let mut next_state = NEXT_STATE.lock().unwrap();
-  *next_state = Some(unsafe { transmute::<Box<dyn AppState>, Box<dyn AppState + Send>>(state) });
+  *next_state = Some(transition);
 }
 
 lazy_static::lazy_static! {
-    static ref NEXT_STATE: Mutex<Option<Box<dyn AppState + Send>>> = Mutex::new(None);
+    static ref NEXT_STATE: Mutex<Option<Transition>> = Mutex::new(None);
 }
 
-#[macroquad::main("MissileSurvivor")]
-async fn main() {
+// Runs before the window is created, so the logger is set up here to report problems with the settings
+fn window_conf() -> Conf {
   env_logger::builder()
     .filter_level(log::LevelFilter::Info)
     .parse_env(Env::new())
     .init();
 
-  let mut state: Box<dyn AppState> = game::Game::new();
+  let settings = Settings::get();
+  Conf {
+    window_title: "MissileSurvivor".to_string(),
+    window_width: settings.window_width,
+    window_height: settings.window_height,
+    fullscreen: settings.fullscreen,
+    ..Conf::default()
+  }
+}
+
+#[macroquad::main(window_conf)]
+async fn main() {
+  // Load the game data right away, so broken definitions are reported at startup
+  game::skills::SkillBook::get();
+  game::difficulty::DifficultyBook::get();
+  audio::Audio::init().await;
+
+  let mut states: Vec<Box<dyn AppState>> = vec![screens::title::TitleScreen::new()];
 
   loop {
-    if let Some(next_state) = NEXT_STATE.lock().unwrap().take() {
-      state = next_state;
+    Diagnostics::update();
+    Frame::update();
+    Input::update();
+
+    if let Some(transition) = NEXT_STATE.lock().unwrap().take() {
+      match transition {
+        Transition::Set(state) => states = vec![state],
+        Transition::Push(state) => states.push(state),
+        Transition::Pop => {
+          states.pop();
+        }
+      }
+    }
+
+    if let Some(state) = states.last_mut() {
+      state.update();
+    }
+
+    for state in &states {
+      state.render();
+    }
+
+    set_default_camera();
+    if Settings::get().show_diagnostics {
+      Diagnostics::render();
     }
-    state.update();
 
     next_frame().await;
   }
@@ -0,0 +1,110 @@
+use macroquad::color;
+use macroquad::prelude::*;
+
+use super::menu::Menu;
+use super::{begin_screen, dim_background};
+use crate::input::{Action, Bindings, Input};
+use crate::settings::Settings;
+use crate::utils::graphics::G;
+use crate::{AppState, pop_state};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Item {
+  Action(Action),
+  Reset,
+  Back,
+}
+
+/// Lets the player bind keys, mouse buttons and gamepad buttons to the input actions.
+/// Every change is stored right away.
+pub struct BindingsScreen {
+  menu: Menu,
+  // Action that waits for the key or button to bind to it
+  rebinding: Option<Action>,
+}
+
+impl BindingsScreen {
+  pub fn new() -> Box<BindingsScreen> {
+    Box::new(Self {
+      menu: Menu::new(-240.0).with_item_size(460.0, 26.0),
+      rebinding: None,
+    })
+  }
+
+  fn items() -> Vec<Item> {
+    let mut items: Vec<Item> = Action::ALL.into_iter().map(Item::Action).collect();
+    items.extend([Item::Reset, Item::Back]);
+    items
+  }
+
+  fn label(&self, item: Item, bindings: &Bindings) -> String {
+    match item {
+      Item::Action(action) if self.rebinding == Some(action) => format!("{}: press a key or button", action.name()),
+      Item::Action(action) => {
+        let names: Vec<String> = bindings.of(action).iter().map(|binding| binding.name()).collect();
+        let names = if names.is_empty() { "-".to_string() } else { names.join(", ") };
+        format!("{}: {names}", action.name())
+      }
+      Item::Reset => "Reset to Defaults".to_string(),
+      Item::Back => "Back".to_string(),
+    }
+  }
+}
+
+impl AppState for BindingsScreen {
+  fn update(&mut self) {
+    if let Some(action) = self.rebinding {
+      // The back action cancels, so its keys and buttons can't be bound while rebinding
+      let (cancel, binding) = {
+        let input = Input::get();
+        (input.is_pressed(Action::Back), input.pressed_binding())
+      };
+      if cancel {
+        self.rebinding = None;
+        return;
+      }
+      if let Some(binding) = binding {
+        let mut settings = Settings::get();
+        settings.bindings.rebind(action, binding);
+        settings.save();
+        self.rebinding = None;
+      }
+      return;
+    }
+
+    if Input::get().is_pressed(Action::Back) {
+      pop_state();
+      return;
+    }
+
+    let items = Self::items();
+    match self.menu.update(items.len()).map(|index| items[index]) {
+      Some(Item::Action(action)) => self.rebinding = Some(action),
+      Some(Item::Reset) => {
+        let mut settings = Settings::get();
+        settings.bindings = Bindings::default();
+        settings.save();
+      }
+      Some(Item::Back) => pop_state(),
+      None => {}
+    }
+  }
+
+  fn render(&self) {
+    let view_rect = begin_screen();
+    dim_background(view_rect);
+
+    G::centered_text("KEY BINDINGS", 0.0, -270.0, 30.0, color::WHITE);
+    let bindings = Settings::get().bindings.clone();
+    let labels: Vec<String> = Self::items().into_iter().map(|item| self.label(item, &bindings)).collect();
+    self.menu.render(&labels);
+
+    let hint = if self.rebinding.is_some() {
+      let back: Vec<String> = bindings.of(Action::Back).iter().map(|binding| binding.name()).collect();
+      format!("{} cancels", back.join(" / "))
+    } else {
+      "Choose an action to bind a key or button to it".to_string()
+    };
+    G::centered_text(&hint, 0.0, 280.0, 16.0, color::GRAY);
+  }
+}
@@ -0,0 +1,143 @@
+use macroquad::color;
+use macroquad::prelude::*;
+
+use super::menu::Menu;
+use super::title::TitleScreen;
+use super::{begin_screen, dim_background, render_high_scores};
+use crate::game::Game;
+use crate::game::skills::SkillBook;
+use crate::game::difficulty::Difficulty;
+use crate::game::stats::RunStats;
+use crate::game::waves::GameMode;
+use crate::game::world::World;
+use crate::highscores::{HighScores, MAX_HIGH_SCORES};
+use crate::input::{Action, Input};
+use crate::utils::format_time;
+use crate::utils::graphics::G;
+use crate::{AppState, set_state};
+
+const PLAY_AGAIN: usize = 0;
+const TITLE: usize = 1;
+
+const ITEMS: [&str; 2] = ["Play Again", "Title Screen"];
+
+/// Summary of a finished run shown on top of the final state of the game
+pub struct GameOverScreen {
+  menu: Menu,
+  game_time: f32,
+  player_level: u32,
+  stats: RunStats,
+  seed: u64,
+  mode: GameMode,
+  difficulty: Difficulty,
+  // Wave the run ended in, in wave mode
+  wave: Option<u32>,
+  replay_message: Option<String>,
+  high_scores: HighScores,
+  // Rank of this run in the high score table
+  rank: Option<usize>,
+}
+
+impl GameOverScreen {
+  /// `high_scores` should already contain this run at `rank`, if it made it into the table
+  pub fn new(world: &World, replay_message: Option<String>, high_scores: HighScores, rank: Option<usize>) -> Box<GameOverScreen> {
+    Box::new(Self {
+      menu: Menu::new(200.0),
+      game_time: world.game_time(),
+      player_level: world.player().player_level(),
+      stats: world.stats().clone(),
+      seed: world.seed(),
+      mode: world.mode(),
+      difficulty: world.difficulty(),
+      wave: world.wave().map(|wave| wave.number),
+      replay_message,
+      high_scores,
+      rank,
+    })
+  }
+}
+
+impl AppState for GameOverScreen {
+  fn update(&mut self) {
+    if Input::get().is_pressed(Action::Back) {
+      set_state(TitleScreen::new());
+      return;
+    }
+
+    match self.menu.update(ITEMS.len()) {
+      Some(PLAY_AGAIN) => set_state(Game::new()),
+      Some(TITLE) => set_state(TitleScreen::new()),
+      _ => {}
+    }
+  }
+
+  fn render(&self) {
+    let view_rect = begin_screen();
+    dim_background(view_rect);
+
+    G::centered_text("GAME OVER", 0.0, -220.0, 40.0, color::WHITE);
+
+    // Summary of this run on the left
+    let x = -200.0;
+    let line_height = 20.0;
+    let time_text = match self.wave {
+      Some(wave) => format!("Survived: {} (wave {wave})", format_time(self.game_time)),
+      None => format!("Survived: {}", format_time(self.game_time)),
+    };
+    G::centered_text(&time_text, x, -150.0, 25.0, color::WHITE);
+
+    let stats = &self.stats;
+    let bunkers_lost = stats
+      .bunkers_lost
+      .iter()
+      .map(|loss| format!("#{} at {}", loss.bunker_idx + 1, format_time(loss.game_time)))
+      .collect::<Vec<_>>()
+      .join(", ");
+    let lines = [
+      format!("Level: {}", self.player_level),
+      format!("Missiles intercepted: {}", stats.missiles_intercepted),
+      format!("Carriers destroyed: {}", stats.carriers_destroyed),
+      format!("Missiles fired: {}", stats.missiles_fired),
+      format!("Accuracy: {:.0}%", stats.accuracy() * 100.0),
+      format!("Longest chain reaction: {}", stats.longest_chain),
+      format!("Highest kill: {:.0} above ground", stats.highest_kill_altitude),
+      format!("Bunkers lost: {bunkers_lost}"),
+      format!("Bunkers rebuilt: {}", stats.bunkers_rebuilt),
+    ];
+    let mut y = -120.0;
+    for line in &lines {
+      G::centered_text(line, x, y, 16.0, color::WHITE);
+      y += line_height;
+    }
+
+    // Experience of the last few builds, most recent first
+    y += 5.0;
+    G::centered_text("Experience per build:", x, y, 16.0, color::GRAY);
+    for build in stats.experience_by_build.iter().rev().take(4) {
+      y += line_height;
+      let skill = build
+        .skill
+        .as_ref()
+        .map_or("start".to_string(), |skill| format!("+{}", SkillBook::get().name(skill)));
+      let build_text = format!("Lv {} ({skill}): {:.0} XP", build.player_level, build.experience);
+      G::centered_text(&build_text, x, y, 16.0, color::WHITE);
+    }
+
+    y += line_height + 10.0;
+    if let Some(rank) = self.rank {
+      let rank_text = format!("New high score: #{}", rank + 1);
+      G::centered_text(&rank_text, x, y, 20.0, color::YELLOW);
+    }
+
+    let seed_text = format!("Seed: {}", self.seed);
+    G::centered_text(&seed_text, 200.0, 130.0, 16.0, color::GRAY);
+    if let Some(replay_message) = &self.replay_message {
+      G::centered_text(replay_message, 200.0, 150.0, 14.0, color::GRAY);
+    }
+
+    // Best runs on the right
+    render_high_scores(&self.high_scores, self.mode, self.difficulty, 200.0, -130.0, MAX_HIGH_SCORES, self.rank);
+
+    self.menu.render(&ITEMS);
+  }
+}
@@ -0,0 +1,88 @@
+use macroquad::color;
+use macroquad::prelude::*;
+
+use crate::game::Game;
+use crate::input::{Action, Input};
+use crate::utils::graphics::G;
+
+const ITEM_WIDTH: f32 = 300.0;
+const ITEM_HEIGHT: f32 = 40.0;
+
+/// A vertical list of entries that can be chosen with the cursor, the keyboard or a gamepad
+pub struct Menu {
+  selected: usize,
+  // y position of the first entry in view coordinates
+  top: f32,
+  item_size: Vec2,
+}
+
+impl Menu {
+  pub fn new(top: f32) -> Self {
+    Self {
+      selected: 0,
+      top,
+      item_size: Vec2::new(ITEM_WIDTH, ITEM_HEIGHT),
+    }
+  }
+
+  /// Use smaller or larger entries, so long menus still fit on the screen
+  pub fn with_item_size(mut self, width: f32, height: f32) -> Self {
+    self.item_size = Vec2::new(width, height);
+    self
+  }
+
+  pub fn selected(&self) -> usize {
+    self.selected
+  }
+
+  fn item_rect(&self, index: usize) -> Rect {
+    let Vec2 { x: width, y: height } = self.item_size;
+    Rect::new(-width / 2.0, self.top + index as f32 * height, width, height)
+  }
+
+  /// Handle input for a menu with `item_count` entries.
+  /// Returns the index of the entry the player chose this frame.
+  pub fn update(&mut self, item_count: usize) -> Option<usize> {
+    if item_count == 0 {
+      return None;
+    }
+
+    let input = Input::get();
+    if input.is_pressed(Action::MenuUp) {
+      self.selected = (self.selected + item_count - 1) % item_count;
+    }
+    if input.is_pressed(Action::MenuDown) {
+      self.selected = (self.selected + 1) % item_count;
+    }
+    if input.is_pressed(Action::Confirm) {
+      return Some(self.selected);
+    }
+
+    // Select the entry under the cursor
+    let (viewport, _) = Game::create_viewport();
+    let cursor = viewport.vec2_to_view(input.cursor());
+    let hovered = (0..item_count).find(|&i| self.item_rect(i).contains(cursor))?;
+    if input.cursor_moved() {
+      self.selected = hovered;
+    }
+    if input.is_pressed(Action::Fire) {
+      self.selected = hovered;
+      return Some(hovered);
+    }
+
+    None
+  }
+
+  pub fn render<S: AsRef<str>>(&self, items: &[S]) {
+    for (i, item) in items.iter().enumerate() {
+      let rect = self.item_rect(i);
+      let is_selected = i == self.selected;
+      let color = if is_selected { color::YELLOW } else { color::WHITE };
+
+      if is_selected {
+        G::rect_outline(rect, 2.0, color::YELLOW);
+      }
+      G::centered_text(item.as_ref(), rect.center().x, rect.center().y, rect.h / 2.0, color);
+    }
+  }
+}
@@ -0,0 +1,84 @@
+use macroquad::color;
+use macroquad::input::show_mouse;
+use macroquad::prelude::Rect;
+
+use crate::game::Game;
+use crate::game::difficulty::Difficulty;
+use crate::game::waves::GameMode;
+use crate::highscores::HighScores;
+use crate::utils::color_ext::ColorExt;
+use crate::utils::format_time;
+use crate::utils::graphics::G;
+use crate::utils::storage::format_date;
+
+pub mod bindings;
+pub mod game_over;
+pub mod menu;
+pub mod paused;
+pub mod settings;
+pub mod title;
+
+/// Set the camera up for drawing a screen in view coordinates and return the visible view rect.
+/// Also brings back the mouse cursor the game hides behind its reticle.
+pub fn begin_screen() -> Rect {
+  show_mouse(true);
+  let (viewport, view_rect) = Game::create_viewport();
+  viewport.set_as_camera();
+  view_rect
+}
+
+/// Darken everything drawn so far, so an overlay stands out against the game below it
+pub fn dim_background(view_rect: Rect) {
+  G::filled_rect(view_rect, color::BLACK.with_alpha(0.7));
+}
+
+/// Draw the first `rows` entries of the high score table of `mode` and `difficulty` centered at `x`, starting at `y`.
+/// The entry with rank `highlight` is drawn in a different color.
+pub fn render_high_scores(
+  high_scores: &HighScores,
+  mode: GameMode,
+  difficulty: Difficulty,
+  x: f32,
+  y: f32,
+  rows: usize,
+  highlight: Option<usize>,
+) {
+  let columns = [x - 140.0, x - 85.0, x - 25.0, x + 35.0, x + 115.0];
+  let row_height = 20.0;
+  let font_size = 16.0;
+
+  let title = format!("HIGH SCORES - {} / {}", mode.name(), difficulty.name()).to_uppercase();
+  G::centered_text(&title, x, y, 20.0, color::YELLOW);
+  // Wave mode runs show the wave they reached instead of the number of hits
+  let header = match mode {
+    GameMode::Endless => ["#", "Time", "Level", "Hits", "Date"],
+    GameMode::Waves => ["#", "Time", "Level", "Wave", "Date"],
+  };
+  for (text, column) in header.iter().zip(columns) {
+    G::centered_text(text, column, y + 25.0, font_size, color::GRAY);
+  }
+
+  let entries = high_scores.entries(mode, difficulty);
+  if entries.is_empty() {
+    G::centered_text("No runs yet", x, y + 25.0 + row_height, font_size, color::WHITE);
+    return;
+  }
+
+  for (rank, entry) in entries.iter().take(rows).enumerate() {
+    let row_y = y + 25.0 + (rank + 1) as f32 * row_height;
+    let color = if highlight == Some(rank) { color::YELLOW } else { color::WHITE };
+    let cells = [
+      format!("{}", rank + 1),
+      format_time(entry.game_time),
+      entry.player_level.to_string(),
+      match mode {
+        GameMode::Endless => entry.missiles_intercepted.to_string(),
+        GameMode::Waves => entry.wave.to_string(),
+      },
+      format_date(entry.timestamp),
+    ];
+    for (text, column) in cells.iter().zip(columns) {
+      G::centered_text(text, column, row_y, font_size, color);
+    }
+  }
+}
@@ -0,0 +1,56 @@
+use macroquad::color;
+use macroquad::prelude::*;
+
+use super::menu::Menu;
+use super::settings::SettingsScreen;
+use super::title::TitleScreen;
+use super::{begin_screen, dim_background};
+use crate::input::{Action, Input};
+use crate::utils::graphics::G;
+use crate::{AppState, pop_state, push_state, set_state};
+
+const RESUME: usize = 0;
+const SETTINGS: usize = 1;
+const QUIT_TO_TITLE: usize = 2;
+
+const ITEMS: [&str; 3] = ["Resume", "Settings", "Quit to Title"];
+
+/// Overlay on top of a running game, which is frozen while this state is active
+pub struct PauseScreen {
+  menu: Menu,
+}
+
+impl PauseScreen {
+  pub fn new() -> Box<PauseScreen> {
+    Box::new(Self { menu: Menu::new(-40.0) })
+  }
+}
+
+impl AppState for PauseScreen {
+  fn update(&mut self) {
+    // The menu reads the input as well, so the lock must be released before it updates
+    let resume = {
+      let input = Input::get();
+      input.is_pressed(Action::Pause) || input.is_pressed(Action::Back)
+    };
+    if resume {
+      pop_state();
+      return;
+    }
+
+    match self.menu.update(ITEMS.len()) {
+      Some(RESUME) => pop_state(),
+      Some(SETTINGS) => push_state(SettingsScreen::new()),
+      Some(QUIT_TO_TITLE) => set_state(TitleScreen::new()),
+      _ => {}
+    }
+  }
+
+  fn render(&self) {
+    let view_rect = begin_screen();
+    dim_background(view_rect);
+
+    G::centered_text("PAUSED", 0.0, -100.0, 40.0, color::WHITE);
+    self.menu.render(&ITEMS);
+  }
+}
@@ -0,0 +1,128 @@
+use macroquad::color;
+use macroquad::prelude::*;
+
+use super::bindings::BindingsScreen;
+use super::menu::Menu;
+use super::{begin_screen, dim_background};
+use crate::input::{Action, Input};
+use crate::settings::Settings;
+use crate::utils::graphics::G;
+use crate::{AppState, pop_state, push_state};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Item {
+  WindowSize,
+  Fullscreen,
+  Volume,
+  EffectsVolume,
+  MusicVolume,
+  Particles,
+  Controls,
+  AimAssist,
+  Diagnostics,
+  Bindings,
+  Back,
+}
+
+/// Lets the player change the settings. Can be opened on top of any other state.
+/// Every change is stored right away.
+pub struct SettingsScreen {
+  menu: Menu,
+}
+
+impl SettingsScreen {
+  pub fn new() -> Box<SettingsScreen> {
+    Box::new(Self { menu: Menu::new(-180.0) })
+  }
+
+  fn items() -> Vec<Item> {
+    let mut items = Vec::new();
+    // The size of the browser canvas is given by the page
+    if cfg!(not(target_arch = "wasm32")) {
+      items.push(Item::WindowSize);
+    }
+    items.extend([
+      Item::Fullscreen,
+      Item::Volume,
+      Item::EffectsVolume,
+      Item::MusicVolume,
+      Item::Particles,
+      Item::Controls,
+      Item::AimAssist,
+      Item::Diagnostics,
+      Item::Bindings,
+      Item::Back,
+    ]);
+    items
+  }
+
+  fn label(item: Item, settings: &Settings) -> String {
+    match item {
+      Item::WindowSize => format!("Window: {}x{}", settings.window_width, settings.window_height),
+      Item::Fullscreen => format!("Fullscreen: {}", on_off(settings.fullscreen)),
+      Item::Volume => format!("Volume: {:.0}%", settings.volume * 100.0),
+      Item::EffectsVolume => format!("Effects: {:.0}%", settings.effects_volume * 100.0),
+      Item::MusicVolume => format!("Music: {:.0}%", settings.music_volume * 100.0),
+      Item::Particles => format!("Particles: {}", settings.effects_intensity.name()),
+      Item::Controls => format!("Controls: {}", settings.control_scheme.name()),
+      Item::AimAssist => format!("Aim Assist: {}", on_off(settings.aim_assist)),
+      Item::Diagnostics => format!("Diagnostics: {}", on_off(settings.show_diagnostics)),
+      Item::Bindings => "Key Bindings".to_string(),
+      Item::Back => "Back".to_string(),
+    }
+  }
+
+  fn change(item: Item) {
+    let mut settings = Settings::get();
+    match item {
+      Item::WindowSize => {
+        (settings.window_width, settings.window_height) = settings.next_window_size();
+        request_new_screen_size(settings.window_width as f32, settings.window_height as f32);
+      }
+      Item::Fullscreen => {
+        settings.fullscreen = !settings.fullscreen;
+        set_fullscreen(settings.fullscreen);
+      }
+      Item::Volume => settings.volume = Settings::next_volume(settings.volume),
+      Item::EffectsVolume => settings.effects_volume = Settings::next_volume(settings.effects_volume),
+      Item::MusicVolume => settings.music_volume = Settings::next_volume(settings.music_volume),
+      Item::Particles => settings.effects_intensity = settings.effects_intensity.next(),
+      Item::Controls => settings.control_scheme = settings.control_scheme.next(),
+      Item::AimAssist => settings.aim_assist = !settings.aim_assist,
+      Item::Diagnostics => settings.show_diagnostics = !settings.show_diagnostics,
+      Item::Bindings | Item::Back => return,
+    }
+    settings.save();
+  }
+}
+
+fn on_off(value: bool) -> &'static str {
+  if value { "On" } else { "Off" }
+}
+
+impl AppState for SettingsScreen {
+  fn update(&mut self) {
+    if Input::get().is_pressed(Action::Back) {
+      pop_state();
+      return;
+    }
+
+    let items = Self::items();
+    match self.menu.update(items.len()).map(|index| items[index]) {
+      Some(Item::Bindings) => push_state(BindingsScreen::new()),
+      Some(Item::Back) => pop_state(),
+      Some(item) => Self::change(item),
+      None => {}
+    }
+  }
+
+  fn render(&self) {
+    let view_rect = begin_screen();
+    dim_background(view_rect);
+
+    G::centered_text("SETTINGS", 0.0, -240.0, 40.0, color::WHITE);
+    let settings = Settings::get();
+    let labels: Vec<String> = Self::items().into_iter().map(|item| Self::label(item, &settings)).collect();
+    self.menu.render(&labels);
+  }
+}
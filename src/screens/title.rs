@@ -0,0 +1,89 @@
+use macroquad::color;
+use macroquad::prelude::*;
+
+use super::menu::Menu;
+use super::settings::SettingsScreen;
+use super::{begin_screen, render_high_scores};
+use crate::game::Game;
+use crate::highscores::HighScores;
+use crate::input::{Action, Input};
+use crate::settings::Settings;
+use crate::utils::color_ext::ColorExt;
+use crate::utils::graphics::G;
+use crate::{AppState, push_state, set_state};
+
+const START: usize = 0;
+const MODE: usize = 1;
+const DIFFICULTY: usize = 2;
+const SETTINGS: usize = 3;
+const QUIT: usize = 4;
+
+/// The first screen after launching the game
+pub struct TitleScreen {
+  menu: Menu,
+  high_scores: HighScores,
+}
+
+impl TitleScreen {
+  pub fn new() -> Box<TitleScreen> {
+    Box::new(Self {
+      menu: Menu::new(-110.0),
+      high_scores: HighScores::load(),
+    })
+  }
+
+  fn items() -> Vec<String> {
+    let settings = Settings::get();
+    let mut items = vec![
+      "Start Game".to_string(),
+      format!("Mode: {}", settings.game_mode.name()),
+      format!("Difficulty: {}", settings.difficulty.name()),
+      "Settings".to_string(),
+    ];
+    // Closing the tab is the way to quit in the browser
+    if cfg!(not(target_arch = "wasm32")) {
+      items.push("Quit".to_string());
+    }
+    items
+  }
+}
+
+impl AppState for TitleScreen {
+  fn update(&mut self) {
+    // Going back from the title screen quits the game
+    #[cfg(not(target_arch = "wasm32"))]
+    if Input::get().is_pressed(Action::Back) {
+      std::process::exit(0);
+    }
+
+    match self.menu.update(Self::items().len()) {
+      Some(START) => set_state(Game::new()),
+      Some(MODE) => {
+        let mut settings = Settings::get();
+        settings.game_mode = settings.game_mode.next();
+        settings.save();
+      }
+      Some(DIFFICULTY) => {
+        let mut settings = Settings::get();
+        settings.difficulty = settings.difficulty.next();
+        settings.save();
+      }
+      Some(SETTINGS) => push_state(SettingsScreen::new()),
+      Some(QUIT) => std::process::exit(0),
+      _ => {}
+    }
+  }
+
+  fn render(&self) {
+    clear_background(BLACK);
+    let view_rect = begin_screen();
+    G::filled_rect(view_rect, color::DARKGRAY.mul(0.5));
+
+    G::centered_text("MISSILE SURVIVOR", 0.0, -200.0, 50.0, color::YELLOW);
+    G::centered_text("Defend your bunkers as long as you can", 0.0, -160.0, 20.0, color::WHITE);
+
+    self.menu.render(&Self::items());
+    let settings = Settings::get().clone();
+    render_high_scores(&self.high_scores, settings.game_mode, settings.difficulty, 0.0, 110.0, 5, None);
+  }
+}
@@ -0,0 +1,219 @@
+use std::sync::{Mutex, MutexGuard};
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::game::difficulty::Difficulty;
+use crate::game::waves::GameMode;
+use crate::input::Bindings;
+use crate::utils::{params, storage};
+
+const STORAGE_KEY: &str = "settings.ron";
+
+/// Window sizes the player can choose from in the settings screen
+pub const WINDOW_SIZES: [(i32, i32); 5] = [(800, 600), (1024, 768), (1280, 720), (1600, 900), (1920, 1080)];
+
+lazy_static! {
+  static ref SETTINGS: Mutex<Settings> = Mutex::new(Settings::load());
+}
+
+/// How the player chooses the bunker that fires
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ControlScheme {
+  /// Firing fires from the nearest bunker with a round loaded
+  MouseNearest,
+  /// The bunker actions (A/S/D or 1/2/3 by default) fire from a specific bunker at the cursor,
+  /// firing fires from the selected bunker and the next bunker action (right-click) selects the next one
+  KeyboardBunkers,
+}
+
+impl ControlScheme {
+  pub fn name(self) -> &'static str {
+    match self {
+      ControlScheme::MouseNearest => "Nearest Bunker",
+      ControlScheme::KeyboardBunkers => "Bunker Keys",
+    }
+  }
+
+  pub fn next(self) -> Self {
+    match self {
+      ControlScheme::MouseNearest => ControlScheme::KeyboardBunkers,
+      ControlScheme::KeyboardBunkers => ControlScheme::MouseNearest,
+    }
+  }
+}
+
+/// How many particles and flashes the game shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EffectsIntensity {
+  Off,
+  Low,
+  Normal,
+  High,
+}
+
+impl EffectsIntensity {
+  pub fn name(self) -> &'static str {
+    match self {
+      EffectsIntensity::Off => "Off",
+      EffectsIntensity::Low => "Low",
+      EffectsIntensity::Normal => "Normal",
+      EffectsIntensity::High => "High",
+    }
+  }
+
+  pub fn next(self) -> Self {
+    match self {
+      EffectsIntensity::Off => EffectsIntensity::Low,
+      EffectsIntensity::Low => EffectsIntensity::Normal,
+      EffectsIntensity::Normal => EffectsIntensity::High,
+      EffectsIntensity::High => EffectsIntensity::Off,
+    }
+  }
+}
+
+/// A setting that was overridden by a launch parameter, together with the value from the settings file
+#[derive(Debug, Clone, Copy)]
+struct Overridden<T> {
+  launch: T,
+  stored: T,
+}
+
+impl<T: Copy + PartialEq> Overridden<T> {
+  fn apply(value: &mut T, launch: T) -> Self {
+    let stored = std::mem::replace(value, launch);
+    Self { launch, stored }
+  }
+
+  // Launch parameters only last for the session, unless the player changed the setting afterwards
+  fn value_to_store(self, current: T) -> T {
+    if current == self.launch { self.stored } else { current }
+  }
+}
+
+#[derive(Debug, Clone, Default)]
+struct LaunchOverrides {
+  fullscreen: Option<Overridden<bool>>,
+  show_diagnostics: Option<Overridden<bool>>,
+  difficulty: Option<Overridden<Difficulty>>,
+}
+
+/// User adjustable options of the game, stored in `settings.ron` in the config directory
+/// (`localStorage` in the web version). Settings missing in the file keep their default.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+  pub window_width: i32,
+  pub window_height: i32,
+  pub fullscreen: bool,
+  /// Master volume from 0.0 to 1.0
+  pub volume: f32,
+  /// Volume of the sound effects relative to the master volume
+  pub effects_volume: f32,
+  /// Volume of the music relative to the master volume
+  pub music_volume: f32,
+  pub effects_intensity: EffectsIntensity,
+  pub show_diagnostics: bool,
+  pub control_scheme: ControlScheme,
+  /// Mark where incoming missiles will be when a shot at the cursor arrives
+  pub aim_assist: bool,
+  /// Keys and buttons of all input actions
+  pub bindings: Bindings,
+  /// Mode of the next run, chosen on the title screen
+  pub game_mode: GameMode,
+  /// Difficulty of the next run, chosen on the title screen
+  pub difficulty: Difficulty,
+  #[serde(skip)]
+  overrides: LaunchOverrides,
+}
+
+impl Default for Settings {
+  fn default() -> Self {
+    Self {
+      window_width: WINDOW_SIZES[0].0,
+      window_height: WINDOW_SIZES[0].1,
+      fullscreen: false,
+      volume: 0.8,
+      effects_volume: 1.0,
+      music_volume: 0.6,
+      effects_intensity: EffectsIntensity::Normal,
+      show_diagnostics: true,
+      control_scheme: ControlScheme::MouseNearest,
+      aim_assist: false,
+      bindings: Bindings::default(),
+      game_mode: GameMode::Endless,
+      difficulty: Difficulty::Normal,
+      overrides: LaunchOverrides::default(),
+    }
+  }
+}
+
+impl Settings {
+  pub fn get() -> MutexGuard<'static, Self> {
+    SETTINGS.lock().unwrap()
+  }
+
+  /// Load the stored settings and apply the launch parameters.
+  /// Missing or unreadable settings are replaced by the defaults.
+  fn load() -> Self {
+    let mut settings = match storage::load_config(STORAGE_KEY) {
+      Some(text) => ron::from_str(&text).unwrap_or_else(|err| {
+        log::error!("Ignoring broken settings: {err}");
+        Self::default()
+      }),
+      None => Self::default(),
+    };
+    settings.apply_launch_parameters();
+    settings
+  }
+
+  fn apply_launch_parameters(&mut self) {
+    if params::has_flag("fullscreen") {
+      self.overrides.fullscreen = Some(Overridden::apply(&mut self.fullscreen, true));
+    }
+    if params::has_flag("no-diagnostics") {
+      self.overrides.show_diagnostics = Some(Overridden::apply(&mut self.show_diagnostics, false));
+    }
+    if let Some(id) = params::get("difficulty") {
+      match Difficulty::from_id(&id) {
+        Some(difficulty) => self.overrides.difficulty = Some(Overridden::apply(&mut self.difficulty, difficulty)),
+        None => log::warn!("Ignoring invalid value '{id}' for parameter 'difficulty'"),
+      }
+    }
+  }
+
+  /// Store the settings. Launch parameters are not stored, so they only apply to this session.
+  pub fn save(&self) {
+    let mut stored = self.clone();
+    let overrides = &self.overrides;
+    if let Some(fullscreen) = overrides.fullscreen {
+      stored.fullscreen = fullscreen.value_to_store(self.fullscreen);
+    }
+    if let Some(show_diagnostics) = overrides.show_diagnostics {
+      stored.show_diagnostics = show_diagnostics.value_to_store(self.show_diagnostics);
+    }
+    if let Some(difficulty) = overrides.difficulty {
+      stored.difficulty = difficulty.value_to_store(self.difficulty);
+    }
+
+    let result = ron::ser::to_string_pretty(&stored, ron::ser::PrettyConfig::default())
+      .map_err(|err| err.to_string())
+      .and_then(|text| storage::save_config(STORAGE_KEY, &text));
+    if let Err(err) = result {
+      log::error!("Could not save settings: {err}");
+    }
+  }
+
+  /// The next larger window size, starting over with the smallest one
+  pub fn next_window_size(&self) -> (i32, i32) {
+    let current = (self.window_width, self.window_height);
+    let next = WINDOW_SIZES.iter().position(|&size| size > current).unwrap_or(0);
+    WINDOW_SIZES[next]
+  }
+
+  /// The given volume raised by 10%, going back to silence after full volume
+  pub fn next_volume(volume: f32) -> f32 {
+    let step = (volume * 10.0).round() as u32;
+    ((step + 1) % 11) as f32 / 10.0
+  }
+}
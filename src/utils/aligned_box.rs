@@ -17,15 +17,14 @@ impl AlignedBoxIt {
     let start_coord = Self::top_left_box(rect.point(), box_size);
     let start = start_coord.to_vec2() * box_size + box_size / 2.;
     let end = Self::bottom_right_box(rect.point() + rect.size(), box_size).to_vec2() * box_size - box_size / 2.;
-    let result = Self {
+    Self {
       start,
       cur_cooord: start_coord,
       start_coord,
       cur: start,
       box_size,
       end,
-    };
-    result
+    }
   }
 
   pub fn top_left_box(pos: Vec2, box_size: Vec2) -> Coord {
@@ -19,9 +19,9 @@ impl Coord {
   }
 }
 
-impl Into<Vec2> for Coord {
-  fn into(self) -> Vec2 {
-    Vec2::new(self.x as f32, self.y as f32)
+impl From<Coord> for Vec2 {
+  fn from(coord: Coord) -> Self {
+    Vec2::new(coord.x as f32, coord.y as f32)
   }
 }
 
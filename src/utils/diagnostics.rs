@@ -111,7 +111,7 @@ impl Diagnostics {
     self.entries.lock().unwrap().insert(
       name,
       Entry {
-        data: data,
+        data,
         epoch_t: self.epoch_t,
       },
     );
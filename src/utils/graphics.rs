@@ -34,11 +34,18 @@ impl G {
     }
   }
 
+  // Like `lines`, but without connecting the last point back to the first one
+  pub fn polyline(points: &[Vec2], thickness: f32, color: Color) {
+    for pair in points.windows(2) {
+      G::line(pair[0], pair[1], thickness, color);
+    }
+  }
+
   pub fn filled_rect(rect: Rect, color: Color) {
     draw_rectangle(rect.x, rect.y, rect.w, rect.h, color);
   }
 
   pub fn rect_outline(rect: Rect, thickness: f32, color: Color) {
-    draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, thickness, color)
+    draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, thickness, color);
   }
 }
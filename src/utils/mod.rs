@@ -6,6 +6,8 @@ pub mod coord;
 pub mod diagnostics;
 pub mod drawing;
 pub mod graphics;
+pub mod params;
+pub mod storage;
 pub mod viewport;
 
 pub use coord::Coord;
@@ -15,6 +17,14 @@ pub fn mouse_pos() -> Vec2 {
   Vec2::new(x, y)
 }
 
+// Format elapsed time as mm:ss
+pub fn format_time(seconds: f32) -> String {
+  let total_seconds = seconds as u32;
+  let minutes = total_seconds / 60;
+  let seconds = total_seconds % 60;
+  format!("{:02}:{:02}", minutes, seconds)
+}
+
 pub fn rect_from_corners(p1: Vec2, p2: Vec2) -> Rect {
   let min = Vec2::new(p1.x.min(p2.x), p1.y.min(p2.y));
   let max = Vec2::new(p1.x.max(p2.x), p1.y.max(p2.y));
@@ -0,0 +1,53 @@
+/// Get a launch parameter given as `--name value` on the command line
+/// or as `?name=value` in the page URL of the web version.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn get(name: &str) -> Option<String> {
+  let flag = format!("--{name}");
+  let mut args = std::env::args().skip(1);
+  while let Some(arg) = args.next() {
+    if arg == flag {
+      return args.next();
+    }
+    if let Some(value) = arg.strip_prefix(&flag).and_then(|rest| rest.strip_prefix('=')) {
+      return Some(value.to_string());
+    }
+  }
+  None
+}
+
+/// Get a launch parameter given as `--name value` on the command line
+/// or as `?name=value` in the page URL of the web version.
+#[cfg(target_arch = "wasm32")]
+pub fn get(name: &str) -> Option<String> {
+  let search = web_sys::window()?.location().search().ok()?;
+  web_sys::UrlSearchParams::new_with_str(&search).ok()?.get(name)
+}
+
+/// Check for a launch flag given as `--name` on the command line
+/// or as `?name` in the page URL of the web version.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn has_flag(name: &str) -> bool {
+  let flag = format!("--{name}");
+  std::env::args().skip(1).any(|arg| arg == flag)
+}
+
+/// Check for a launch flag given as `--name` on the command line
+/// or as `?name` in the page URL of the web version.
+#[cfg(target_arch = "wasm32")]
+pub fn has_flag(name: &str) -> bool {
+  let search = web_sys::window().and_then(|window| window.location().search().ok());
+  let params = search.and_then(|search| web_sys::UrlSearchParams::new_with_str(&search).ok());
+  params.is_some_and(|params| params.has(name))
+}
+
+/// Get a launch parameter and parse it, ignoring values that fail to parse
+pub fn get_parsed<T: std::str::FromStr>(name: &str) -> Option<T> {
+  let value = get(name)?;
+  match value.parse() {
+    Ok(parsed) => Some(parsed),
+    Err(_) => {
+      log::warn!("Ignoring invalid value '{value}' for parameter '{name}'");
+      None
+    }
+  }
+}
@@ -0,0 +1,123 @@
+//! Persistent key/value storage. On native every key is a file in the data or config directory
+//! of the game, in the browser the keys live in `localStorage`.
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+
+const APP_NAME: &str = "missile-survivor";
+
+#[cfg(not(target_arch = "wasm32"))]
+fn env_dir(name: &str) -> Option<PathBuf> {
+  std::env::var_os(name).filter(|dir| !dir.is_empty()).map(PathBuf::from)
+}
+
+/// Directory for the files of the game, following the conventions of the platform
+#[cfg(not(target_arch = "wasm32"))]
+pub fn data_dir() -> PathBuf {
+  let base = if cfg!(target_os = "windows") {
+    env_dir("APPDATA")
+  } else if cfg!(target_os = "macos") {
+    env_dir("HOME").map(|home| home.join("Library/Application Support"))
+  } else {
+    env_dir("XDG_DATA_HOME").or_else(|| env_dir("HOME").map(|home| home.join(".local/share")))
+  };
+
+  base.unwrap_or_else(|| PathBuf::from(".")).join(APP_NAME)
+}
+
+/// Directory for the configuration of the game. Only Linux separates it from the data directory.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn config_dir() -> PathBuf {
+  if cfg!(target_os = "windows") || cfg!(target_os = "macos") {
+    return data_dir();
+  }
+  let base = env_dir("XDG_CONFIG_HOME").or_else(|| env_dir("HOME").map(|home| home.join(".config")));
+  base.unwrap_or_else(|| PathBuf::from(".")).join(APP_NAME)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn load_from(dir: PathBuf, key: &str) -> Option<String> {
+  std::fs::read_to_string(dir.join(key)).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn save_to(dir: PathBuf, key: &str, value: &str) -> Result<(), String> {
+  std::fs::create_dir_all(&dir).map_err(|err| format!("Could not create '{}': {err}", dir.display()))?;
+  let path = dir.join(key);
+  std::fs::write(&path, value).map_err(|err| format!("Could not write '{}': {err}", path.display()))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load(key: &str) -> Option<String> {
+  load_from(data_dir(), key)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save(key: &str, value: &str) -> Result<(), String> {
+  save_to(data_dir(), key, value)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_config(key: &str) -> Option<String> {
+  load_from(config_dir(), key)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_config(key: &str, value: &str) -> Result<(), String> {
+  save_to(config_dir(), key, value)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+  web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn load(key: &str) -> Option<String> {
+  local_storage()?.get_item(&format!("{APP_NAME}/{key}")).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn save(key: &str, value: &str) -> Result<(), String> {
+  let storage = local_storage().ok_or("localStorage is not available")?;
+  storage
+    .set_item(&format!("{APP_NAME}/{key}"), value)
+    .map_err(|err| format!("Could not write '{key}' to localStorage: {err:?}"))
+}
+
+// The browser has no separate place for configuration
+#[cfg(target_arch = "wasm32")]
+pub fn load_config(key: &str) -> Option<String> {
+  load(key)
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn save_config(key: &str, value: &str) -> Result<(), String> {
+  save(key, value)
+}
+
+/// Current time as seconds since the unix epoch
+pub fn unix_time() -> u64 {
+  #[cfg(target_arch = "wasm32")]
+  return (js_sys::Date::now() / 1000.0) as u64;
+
+  #[cfg(not(target_arch = "wasm32"))]
+  std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .map_or(0, |duration| duration.as_secs())
+}
+
+/// Format seconds since the unix epoch as YYYY-MM-DD (UTC)
+pub fn format_date(unix_time: u64) -> String {
+  // Civil from days, see http://howardhinnant.github.io/date_algorithms.html
+  let days = (unix_time / 86400) as i64 + 719468;
+  let era = days.div_euclid(146097);
+  let day_of_era = days.rem_euclid(146097);
+  let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+  let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let month_index = (5 * day_of_year + 2) / 153;
+  let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+  let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+  let year = year_of_era + era * 400 + i64::from(month <= 2);
+  format!("{year:04}-{month:02}-{day:02}")
+}
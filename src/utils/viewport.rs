@@ -1,8 +1,7 @@
 use macroquad::{
-  math::{vec2, FloatExt},
+  math::{vec2, Affine2, FloatExt},
   prelude::{set_camera, Camera2D, Rect, Vec2},
 };
-use rand_distr::num_traits::Zero;
 use crate::game::frame::Frame;
 
 #[derive(Clone, Copy, Debug)]
@@ -43,9 +42,9 @@ impl View {
   }
 }
 
-impl Into<Viewport> for View {
-  fn into(self) -> Viewport {
-    Viewport::make(self)
+impl From<View> for Viewport {
+  fn from(view: View) -> Self {
+    Viewport::make(view)
   }
 }
 
@@ -167,20 +166,26 @@ impl Viewport {
     len / self.project_factor()
   }
 
+  /// Transform from view to screen coordinates. It matches the camera of `set_as_camera`,
+  /// which rotates the view around its center before scaling it to the screen.
+  pub fn view_to_screen(&self) -> Affine2 {
+    Affine2::from_translation(self.screen_size / 2.)
+      * Affine2::from_scale(self.screen_size / self.view_size)
+      * Affine2::from_angle(self.rotation.to_radians())
+      * Affine2::from_translation(-self.center)
+  }
+
+  /// Transform from screen to view coordinates, the inverse of `view_to_screen`
+  pub fn screen_to_view(&self) -> Affine2 {
+    self.view_to_screen().inverse()
+  }
+
   pub fn vec2_to_screen(&self, pos: Vec2) -> Vec2 {
-    assert!(self.rotation.is_zero());
-    let view_size = self.view_size();
-    let rel_pos = (pos - self.center) / (view_size / 2.);
-    let half_screen_size = self.screen_size / 2.;
-    half_screen_size + rel_pos * half_screen_size
+    self.view_to_screen().transform_point2(pos)
   }
 
   pub fn vec2_to_view(&self, pos: Vec2) -> Vec2 {
-    assert!(self.rotation.is_zero());
-    let view_size = self.view_size();
-    let half_screen_size = self.screen_size / 2.;
-    let rel_pos = (pos - half_screen_size) / half_screen_size;
-    self.center + rel_pos * (view_size / 2.)
+    self.screen_to_view().transform_point2(pos)
   }
 
   pub fn view_rect(&self) -> Rect {
@@ -210,3 +215,120 @@ impl Viewport {
     });
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use macroquad::camera::Camera;
+  use macroquad::math::vec3;
+
+  use super::*;
+
+  const MODES: [ViewportMode; 2] = [ViewportMode::FitScreenIntoView, ViewportMode::FitViewIntoScreen];
+  const SCREEN_SIZES: [Vec2; 5] = [
+    vec2(800., 600.),
+    vec2(600., 800.),
+    vec2(1920., 1080.),
+    vec2(1080., 1920.),
+    vec2(500., 500.),
+  ];
+  const ROTATIONS: [f32; 5] = [0., 17., -90., 180., 311.];
+
+  fn assert_close(actual: Vec2, expected: Vec2) {
+    let tolerance = 1e-3 * expected.length().max(1.);
+    assert!(actual.distance(expected) <= tolerance, "expected {expected}, got {actual}");
+  }
+
+  // Every combination of mode, screen size and rotation, for views created with `fit` and `new`
+  fn viewports() -> Vec<Viewport> {
+    let view_rect = Rect::new(-400., -300., 800., 600.);
+    let mut viewports = Vec::new();
+    for mode in MODES {
+      for screen_size in SCREEN_SIZES {
+        for rotation in ROTATIONS {
+          viewports.push(Viewport::fit(view_rect, screen_size, rotation, mode));
+          let view = View::new(vec2(30., -20.), 0.01, rotation);
+          viewports.push(Viewport::new(view, screen_size, mode));
+        }
+      }
+    }
+    viewports
+  }
+
+  fn points(viewport: &Viewport) -> Vec<Vec2> {
+    let size = viewport.view_size();
+    [vec2(0., 0.), vec2(0.5, 0.5), vec2(-0.5, 0.25), vec2(0.1, -0.4), vec2(-0.3, -0.3)]
+      .into_iter()
+      .map(|offset| viewport.pos() + offset * size)
+      .collect()
+  }
+
+  #[test]
+  fn view_points_round_trip_through_screen_space() {
+    for viewport in viewports() {
+      for point in points(&viewport) {
+        assert_close(viewport.vec2_to_view(viewport.vec2_to_screen(point)), point);
+      }
+    }
+  }
+
+  #[test]
+  fn screen_points_round_trip_through_view_space() {
+    for viewport in viewports() {
+      let screen_size = viewport.screen_size();
+      for point in [vec2(0., 0.), screen_size / 2., screen_size, vec2(screen_size.x * 0.2, screen_size.y * 0.9)] {
+        assert_close(viewport.vec2_to_screen(viewport.vec2_to_view(point)), point);
+      }
+    }
+  }
+
+  #[test]
+  fn transforms_are_inverse() {
+    for viewport in viewports() {
+      let identity = viewport.view_to_screen() * viewport.screen_to_view();
+      assert!(identity.abs_diff_eq(Affine2::IDENTITY, 1e-3), "{identity} is not the identity");
+    }
+  }
+
+  #[test]
+  fn view_center_is_screen_center() {
+    for viewport in viewports() {
+      assert_close(viewport.vec2_to_screen(viewport.pos()), viewport.screen_size() / 2.);
+    }
+  }
+
+  #[test]
+  fn transform_matches_camera() {
+    for viewport in viewports() {
+      let view_size = viewport.view_size();
+      let camera = Camera2D {
+        target: viewport.pos(),
+        zoom: vec2(2. / view_size.x, 2. / view_size.y),
+        offset: vec2(0., 0.),
+        rotation: viewport.rotation(),
+        render_target: None,
+        viewport: None,
+      };
+      for point in points(&viewport) {
+        // Normalized device coordinates to screen coordinates, like `Camera2D::world_to_screen`
+        let ndc = camera.matrix().transform_point3(vec3(point.x, point.y, 0.));
+        let screen_size = viewport.screen_size();
+        let expected = vec2((ndc.x / 2. + 0.5) * screen_size.x, (0.5 - ndc.y / 2.) * screen_size.y);
+        assert_close(viewport.vec2_to_screen(point), expected);
+      }
+    }
+  }
+
+  #[test]
+  fn fitted_view_rect_is_visible() {
+    let view_rect = Rect::new(-400., -300., 800., 600.);
+    for screen_size in SCREEN_SIZES {
+      let viewport = Viewport::fit(view_rect, screen_size, 0., ViewportMode::FitViewIntoScreen);
+      for corner in [view_rect.point(), view_rect.point() + view_rect.size()] {
+        let screen_pos = viewport.vec2_to_screen(corner);
+        let tolerance = 1e-3;
+        let on_screen = screen_pos.cmpge(Vec2::splat(-tolerance)).all() && screen_pos.cmple(screen_size + tolerance).all();
+        assert!(on_screen, "{corner} is off screen at {screen_pos} for a screen of {screen_size}");
+      }
+    }
+  }
+}